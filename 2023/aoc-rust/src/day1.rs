@@ -1,89 +1,208 @@
-pub fn run(part: u8) {
+use std::collections::{HashMap, VecDeque};
+
+pub fn run(part: u8, args: &[String]) {
     match part {
         1 => part1(),
         2 => part2(),
+        3 => part3(args),
         _ => println!("Part {} not implemented", part),
     }
 }
 
-// PART 1
-fn get_calibration_number_p1(line: &str) -> u32 {
-    let nums: Vec<_> = line.chars().filter_map(|c| c.to_digit(10)).collect();
-    let first = nums.as_slice().first().unwrap();
-    let last = nums.as_slice().last().unwrap();
-    (10 * first) + last
+const DIGITS: [(&str, u32); 10] = [
+    ("0", 0),
+    ("1", 1),
+    ("2", 2),
+    ("3", 3),
+    ("4", 4),
+    ("5", 5),
+    ("6", 6),
+    ("7", 7),
+    ("8", 8),
+    ("9", 9),
+];
+
+const ENGLISH: [(&str, u32); 10] = [
+    ("zero", 0),
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+// Where a word was found in a line, as a byte range.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Span {
+    start: usize,
+    end: usize,
+    value: u32,
 }
 
-fn get_cal_numbers_p1() -> Vec<u32> {
-    let input = include_str!("../../puzzle_input/d1").trim();
-    input
-        .lines()
-        .map(|l: &str| get_calibration_number_p1(l))
-        .collect()
+#[derive(Clone, Debug, Default)]
+struct Node {
+    children: HashMap<u8, usize>,
+    // The longest proper suffix of this node's text that's also in the trie.
+    fail: usize,
+    // The words ending here, as (length, value), including those that end
+    // on a suffix.
+    words: Vec<(usize, u32)>,
 }
 
-fn part1() {
-    println!("{}", get_cal_numbers_p1().into_iter().sum::<u32>())
+// Finds every word in a vocabulary in one pass over a line, Aho-Corasick
+// style. Words can overlap, so "twone" gives both "two" and "one", and "eightwo"
+// both "eight" and "two".
+struct DigitMatcher {
+    nodes: Vec<Node>,
 }
 
-// PART 2
-fn parse_num(line: &[u8]) -> Option<u32> {
-    let first_char = line[0] as char;
-
-    if let Some(digit) = first_char.to_digit(10) {
-        Some(digit)
-    } else if line.starts_with(b"one") {
-        Some(1)
-    } else if line.starts_with(b"two") {
-        Some(2)
-    } else if line.starts_with(b"three") {
-        Some(3)
-    } else if line.starts_with(b"four") {
-        Some(4)
-    } else if line.starts_with(b"five") {
-        Some(5)
-    } else if line.starts_with(b"six") {
-        Some(6)
-    } else if line.starts_with(b"seven") {
-        Some(7)
-    } else if line.starts_with(b"eight") {
-        Some(8)
-    } else if line.starts_with(b"nine") {
-        Some(9)
-    } else if line.starts_with(b"zero") {
-        Some(0)
-    } else {
-        None
+impl DigitMatcher {
+    fn new(vocabulary: &[(&str, u32)]) -> Self {
+        let mut nodes = vec![Node::default()];
+        for &(word, value) in vocabulary {
+            let mut node = 0;
+            for &b in word.as_bytes() {
+                node = match nodes[node].children.get(&b) {
+                    Some(&child) => child,
+                    None => {
+                        nodes.push(Node::default());
+                        let child = nodes.len() - 1;
+                        nodes[node].children.insert(b, child);
+                        child
+                    }
+                };
+            }
+            nodes[node].words.push((word.len(), value));
+        }
+
+        // Fill in the fail links breadth first, so each node's parent and
+        // everything shorter is done before it.
+        let mut queue: VecDeque<usize> = nodes[0].children.values().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            let children: Vec<(u8, usize)> =
+                nodes[node].children.iter().map(|(&b, &c)| (b, c)).collect();
+            for (b, child) in children {
+                let mut fail = nodes[node].fail;
+                let fail = loop {
+                    match nodes[fail].children.get(&b) {
+                        Some(&next) => break next,
+                        None if fail == 0 => break 0,
+                        None => fail = nodes[fail].fail,
+                    }
+                };
+                nodes[child].fail = fail;
+                let inherited = nodes[fail].words.clone();
+                nodes[child].words.extend(inherited);
+                queue.push_back(child);
+            }
+        }
+
+        Self { nodes }
     }
-}
 
-fn get_line_nums(line: &str) -> Vec<u32> {
-    let bytes = line.as_bytes();
-    let mut result = Vec::new();
+    fn with_words(words: &[(&str, u32)]) -> Self {
+        Self::new(&[&DIGITS[..], words].concat())
+    }
 
-    for i in 0..bytes.len() {
-        if let Some(num) = parse_num(&bytes[i..]) {
-            result.push(num)
+    // Every word in the line, in the order they start.
+    fn find_all(&self, line: &str) -> Vec<Span> {
+        let mut spans = vec![];
+        let mut node = 0;
+        for (i, &b) in line.as_bytes().iter().enumerate() {
+            node = loop {
+                match self.nodes[node].children.get(&b) {
+                    Some(&next) => break next,
+                    None if node == 0 => break 0,
+                    None => node = self.nodes[node].fail,
+                }
+            };
+            spans.extend(self.nodes[node].words.iter().map(|&(len, value)| Span {
+                start: i + 1 - len,
+                end: i + 1,
+                value,
+            }));
         }
+        spans.sort();
+        spans
+    }
+
+    fn calibration_value(&self, line: &str) -> Option<u32> {
+        let spans = self.find_all(line);
+        let first = spans.first()?;
+        let last = spans.last()?;
+        Some(10 * first.value + last.value)
     }
-    result
-}
 
-fn get_calibration_number_p2(line: &str) -> u32 {
-    let nums = get_line_nums(line);
-    let first = nums.as_slice().first().unwrap();
-    let last = nums.as_slice().last().unwrap();
-    (10 * first) + last
+    fn total(&self, input: &str) -> u32 {
+        input
+            .lines()
+            .map(|line| {
+                self.calibration_value(line)
+                    .unwrap_or_else(|| panic!("No digits in '{}'", line))
+            })
+            .sum()
+    }
 }
 
-fn get_cal_numbers_p2() -> Vec<u32> {
+fn part1() {
     let input = include_str!("../../puzzle_input/d1").trim();
-    input
-        .lines()
-        .map(|l: &str| get_calibration_number_p2(l))
-        .collect()
+    println!("{}", DigitMatcher::with_words(&[]).total(input))
 }
 
 fn part2() {
-    println!("{}", get_cal_numbers_p2().into_iter().sum::<u32>());
+    let input = include_str!("../../puzzle_input/d1").trim();
+    println!("{}", DigitMatcher::with_words(&ENGLISH).total(input));
+}
+
+// Usage: [--words WORD=DIGIT,...] [LINE...]
+//
+// Show the words found in each line, along with its calibration value. The
+// words default to the English ones from part 2, and digits always count.
+fn part3(args: &[String]) {
+    let mut words: Vec<(String, u32)> = ENGLISH.iter().map(|&(w, v)| (w.to_string(), v)).collect();
+    let mut lines = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--words" => {
+                let spec = args.next().expect("Missing value for argument");
+                words = spec
+                    .split(',')
+                    .map(|word| {
+                        let (word, value) = word.split_once('=').expect("Expected WORD=DIGIT");
+                        (word.to_string(), value.parse().expect("Expected a number"))
+                    })
+                    .collect();
+            }
+            _ => lines.push(arg.as_str()),
+        }
+    }
+    if lines.is_empty() {
+        lines = include_str!("../../puzzle_input/d1")
+            .trim()
+            .lines()
+            .collect();
+    }
+
+    let words: Vec<(&str, u32)> = words.iter().map(|(w, v)| (w.as_str(), *v)).collect();
+    let matcher = DigitMatcher::with_words(&words);
+    let mut total = 0;
+    for line in lines {
+        let spans = matcher.find_all(line);
+        let value = matcher.calibration_value(line);
+        total += value.unwrap_or(0);
+
+        let found: Vec<String> = spans
+            .iter()
+            .map(|s| format!("{}@{}..{}", &line[s.start..s.end], s.start, s.end))
+            .collect();
+        let value = value.map_or("-".to_string(), |v| v.to_string());
+        println!("{:>3}  {}  {}", value, line, found.join(" "));
+    }
+    println!("Total: {}", total);
 }
@@ -1,11 +1,14 @@
-use std::collections::{HashSet, VecDeque};
+use std::fmt::Display;
 
 use itertools::Itertools;
 
+use crate::{grid::GridBitSet, polygon::Polygon};
+
 pub fn run(part: u8) {
     match part {
         1 => part1(),
         2 => part2(),
+        3 => render(),
         _ => println!("Part {} not implemented", part),
     }
 }
@@ -25,7 +28,7 @@ impl Loop {
         let loop_positions = self.loop_positions();
         for row in 0..self.map_height {
             for col in 0..self.map_width {
-                if !loop_positions.contains(&(row, col)) {
+                if !loop_positions.contains((row, col)) {
                     self.data[row][col] = '.';
                 }
             }
@@ -33,33 +36,62 @@ impl Loop {
     }
 
     fn find_farthest_pos_distance(&self) -> usize {
+        self.loop_path().len() / 2
+    }
+
+    // Walk the loop starting from the 'S' tile, returning every tile position
+    // in the order it is visited.
+    fn loop_path(&self) -> Vec<Position> {
         let (_, prev_pos) = self.get_pipe_in_out_positions(self.start);
+        let mut path = vec![self.start];
 
         let mut curr_pos = self.next_pipe_pos(self.start, prev_pos);
         let mut prev_pos = self.start;
-        let mut count = 1;
-
         while curr_pos != self.start {
+            path.push(curr_pos);
             (curr_pos, prev_pos) = (self.next_pipe_pos(curr_pos, prev_pos), curr_pos);
-            count += 1;
         }
 
-        count / 2
+        path
     }
 
-    fn loop_positions(&self) -> HashSet<Position> {
-        let (_, prev_pos) = self.get_pipe_in_out_positions(self.start);
-        let mut loop_positions = HashSet::new();
-        loop_positions.insert(self.start);
+    fn loop_positions(&self) -> GridBitSet {
+        let mut loop_positions = GridBitSet::new(self.map_height, self.map_width);
+        for pos in self.loop_path() {
+            loop_positions.insert(pos);
+        }
+        loop_positions
+    }
 
-        let mut curr_pos = self.next_pipe_pos(self.start, prev_pos);
-        let mut prev_pos = self.start;
-        while curr_pos != self.start {
-            loop_positions.insert(curr_pos);
-            (curr_pos, prev_pos) = (self.next_pipe_pos(curr_pos, prev_pos), curr_pos);
+    // Treat the loop as a polygon through the centres of its tiles.
+    fn to_polygon(&self) -> Polygon {
+        let path = self
+            .loop_path()
+            .into_iter()
+            .map(|(r, c)| (r as i64, c as i64))
+            .collect_vec();
+        Polygon::from_path(&path)
+    }
+
+    fn enclosed_tile_count(&self) -> usize {
+        self.to_polygon().interior_points() as usize
+    }
+
+    fn interior_positions(&self) -> GridBitSet {
+        let polygon = self.to_polygon();
+        let ((min_row, min_col), _) = polygon.bounding_box();
+
+        let mut interior_positions = GridBitSet::new(self.map_height, self.map_width);
+        for (r, row) in polygon.rasterise().into_iter().enumerate() {
+            for (c, inside) in row.into_iter().enumerate() {
+                if inside {
+                    interior_positions.insert((r + min_row as usize, c + min_col as usize));
+                }
+            }
         }
+        interior_positions.difference_with(&self.loop_positions());
 
-        loop_positions
+        interior_positions
     }
 
     // Use this to find the tile under the 'S' tile.
@@ -95,26 +127,6 @@ impl Loop {
         nbrs
     }
 
-    fn board_neighbour_positions(&self, pos: Position) -> Vec<Position> {
-        let (r, c) = pos;
-        let mut nbrs = vec![];
-
-        if r > 0 {
-            nbrs.push((r - 1, c));
-        }
-        if r < self.map_height - 1 {
-            nbrs.push((r + 1, c));
-        }
-        if c > 0 {
-            nbrs.push((r, c - 1));
-        }
-        if c < self.map_width - 1 {
-            nbrs.push((r, c + 1));
-        }
-
-        nbrs
-    }
-
     // Given a pipe tile position, return the two tile positions that feed into it.
     fn get_pipe_in_out_positions(&self, pos: Position) -> (Position, Position) {
         let (r, c) = pos;
@@ -191,92 +203,38 @@ impl Loop {
             panic!("Position cannot be in loop!")
         }
     }
+}
 
-    // Double to size of the board. This makes separating the interior from the
-    // exterior easier.
-    //
-    // Use the '*' character to denote squares we don't want to copy, to ensure
-    // that the double board size has exactly 4x the number of '.' characters.
-    fn stretch_board(&mut self) {
-        let mut res = vec![];
-
-        for row in self.data.iter() {
-            let mut s = Vec::new();
-            for &c in row {
-                if ".-".contains(c) {
-                    s.push(c);
-                    s.push(c);
-                } else if c == '|' {
-                    s.push(c);
-                    s.push('*');
-                } else if "FL".contains(c) {
-                    s.push(c);
-                    s.push('-');
-                } else if "7J".contains(c) {
-                    s.push(c);
-                    s.push('*');
-                } else {
-                    panic!("Invalid character '{}'", c);
-                }
-            }
-            res.push(s.clone());
-            res.push(s.clone());
-        }
-
-        for row in (0..res.len()).step_by(2) {
-            for col in 0..res[0].len() {
-                let c = res[row][col];
-
-                if ".|*".contains(c) {
-                    res[row + 1][col] = c;
-                } else if c == '-' {
-                    res[row + 1][col] = '*';
-                } else if "F7".contains(c) {
-                    res[row + 1][col] = '|';
-                } else if "LJ".contains(c) {
-                    res[row + 1][col] = '*';
-                } else {
-                    panic!("Invalid character '{}'", c);
-                }
-            }
-        }
-
-        self.data = res;
-        self.map_height *= 2;
-        self.map_width *= 2;
-        self.start = (self.start.0 * 2, self.start.1 * 2);
-    }
-
-    fn get_exterior_positions(&self) -> HashSet<Position> {
-        let mut exterior_positions = HashSet::new();
-
-        let is_border_position =
-            |(r, c)| r == 0 || r == self.map_height - 1 || c == 0 || c == self.map_width - 1;
-
-        let mut q = VecDeque::new();
-        for r in 0..self.map_height {
-            for c in 0..self.map_width {
-                if is_border_position((r, c)) && "*.".contains(self.data[r][c]) {
-                    exterior_positions.insert((r, c));
-                    q.push_back((r, c));
-                }
-            }
-        }
-
-        while !q.is_empty() {
-            let pos = q.pop_front().unwrap();
-            exterior_positions.insert(pos);
-
-            let nbrs = self.board_neighbour_positions(pos);
-            for n in nbrs {
-                let c = self.data[n.0][n.1];
-                if !exterior_positions.contains(&n) && "*.".contains(c) {
-                    q.push_back(n);
-                    exterior_positions.insert(n);
-                }
-            }
-        }
-        exterior_positions
+// Draw the loop with box-drawing characters, marking tiles inside the loop
+// with 'I' and tiles outside of it with 'O'.
+impl Display for Loop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let loop_positions = self.loop_positions();
+        let interior_positions = self.interior_positions();
+
+        let s = (0..self.map_height)
+            .map(|r| {
+                (0..self.map_width)
+                    .map(|c| {
+                        if interior_positions.contains((r, c)) {
+                            return 'I';
+                        } else if !loop_positions.contains((r, c)) {
+                            return 'O';
+                        }
+                        match self.data[r][c] {
+                            '|' => '│',
+                            '-' => '─',
+                            'F' => '┌',
+                            '7' => '┐',
+                            'L' => '└',
+                            'J' => '┘',
+                            c => c,
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .join("\n");
+        write!(f, "{}", s)
     }
 }
 
@@ -315,15 +273,11 @@ fn part1() {
 }
 
 fn part2() {
-    let mut l = parse_loop();
-    l.stretch_board();
-    let exterior_positions = l.get_exterior_positions();
-    let interior_position_count = (0..l.map_height)
-        .cartesian_product(0..l.map_width)
-        .filter(|pos| !exterior_positions.contains(pos))
-        .filter(|&(r, c)| l.data[r][c] == '.') // make sure to ignore '*' chars
-        .count();
-
-    // stretched board has 4x as many '.' characters
-    println!("{}", interior_position_count / 4);
+    let l = parse_loop();
+    println!("{}", l.enclosed_tile_count());
+}
+
+fn render() {
+    let l = parse_loop();
+    println!("{}", l);
 }
@@ -1,10 +1,12 @@
-use cached::proc_macro::cached;
+use std::iter;
+
 use itertools::Itertools;
 
-pub fn run(part: u8) {
+pub fn run(part: u8, args: &[String]) {
     match part {
         1 => part1(),
         2 => part2(),
+        3 => inspect(args),
         _ => println!("Part {} not implemented", part),
     }
 }
@@ -32,62 +34,118 @@ impl From<&str> for Records {
     }
 }
 
-#[cached]
-fn num_arrangements(prev_char: char, suffix: String, counts: Vec<usize>) -> usize {
-    let can_place_cluster = |s: &str, cluster_size: usize| -> bool {
-        let following_char = s.chars().nth(cluster_size);
-        s.len() >= cluster_size && !s[..cluster_size].contains('.') && following_char != Some('#')
-    };
+impl Record {
+    // If a group of damaged springs can start at position `i`, return where
+    // the next group could start.
+    fn place_group(&self, i: usize, group_size: usize) -> Option<usize> {
+        let springs = self.rec.as_bytes();
+        let group_end = i + group_size;
+        let can_place_group = group_end <= springs.len()
+            && !springs[i..group_end].contains(&b'.')
+            && springs.get(group_end) != Some(&b'#');
+        can_place_group.then_some((group_end + 1).min(springs.len()))
+    }
 
-    if suffix.is_empty() {
-        return if counts.is_empty() { 1 } else { 0 };
-    } else if counts.is_empty() {
-        return if suffix.contains('#') { 0 } else { 1 };
-    } else if &suffix[0..1] == "." {
-        return num_arrangements('.', suffix[1..].to_string(), counts);
-    } else if &suffix[0..1] == "#" {
-        if can_place_cluster(&suffix, counts[0]) {
-            return num_arrangements('#', suffix[counts[0]..].to_string(), counts[1..].to_vec());
-        } else {
-            return 0;
+    // ways[i][j] is the number of ways to fit the groups `check[j..]` into the
+    // springs `rec[i..]`, filled in from the end of the record backwards.
+    fn arrangement_table(&self) -> Vec<Vec<usize>> {
+        let springs = self.rec.as_bytes();
+        let (n, m) = (springs.len(), self.check.len());
+
+        let mut ways = vec![vec![0; m + 1]; n + 1];
+        ways[n][m] = 1;
+        for i in (0..n).rev() {
+            for j in 0..=m {
+                // put a dot down
+                if springs[i] != b'#' {
+                    ways[i][j] += ways[i + 1][j];
+                }
+                // put a group down, followed by a dot unless we've hit the end
+                if j < m && springs[i] != b'.' {
+                    if let Some(next) = self.place_group(i, self.check[j]) {
+                        ways[i][j] += ways[next][j + 1];
+                    }
+                }
+            }
         }
-    } else {
-        let mut num_possible_arrangements = 0;
-
-        let cluster_size = counts[0];
-        if prev_char != '#' && can_place_cluster(&suffix, cluster_size) {
-            num_possible_arrangements += num_arrangements(
-                '#',
-                suffix[cluster_size..].to_string(),
-                counts[1..].to_vec(),
-            );
+        ways
+    }
+
+    fn possible_arrangement_count(&self) -> usize {
+        self.arrangement_table()[0][0]
+    }
+
+    // From position `i` with groups `check[j..]` still to place, the choices
+    // that lead to at least one arrangement, as (text, next i, next j). A
+    // group comes before a dot, so arrangements come out in lexicographic
+    // order ('#' before '.').
+    fn choices(&self, ways: &[Vec<usize>], i: usize, j: usize) -> Vec<(String, usize, usize)> {
+        let springs = self.rec.as_bytes();
+        let mut choices = vec![];
+
+        if j < self.check.len() && springs[i] != b'.' {
+            if let Some(next) = self.place_group(i, self.check[j]) {
+                if ways[next][j + 1] > 0 {
+                    let group = "#".repeat(self.check[j]) + &".".repeat(next - i - self.check[j]);
+                    choices.push((group, next, j + 1));
+                }
+            }
+        }
+        if springs[i] != b'#' && ways[i + 1][j] > 0 {
+            choices.push((".".to_string(), i + 1, j));
         }
 
-        // case where we put a dot down instead of the cluster
-        num_possible_arrangements +=
-            num_arrangements('.', suffix[1..].to_string(), counts.to_vec());
+        choices
+    }
+
+    fn arrangements(&self) -> impl Iterator<Item = String> + '_ {
+        let ways = self.arrangement_table();
+        let n = self.rec.len();
+        let mut stack = if ways[0][0] > 0 {
+            vec![(String::new(), 0, 0)]
+        } else {
+            vec![]
+        };
 
-        return num_possible_arrangements;
+        iter::from_fn(move || {
+            while let Some((prefix, i, j)) = stack.pop() {
+                if i == n {
+                    return Some(prefix);
+                }
+                for (text, next_i, next_j) in self.choices(&ways, i, j).into_iter().rev() {
+                    stack.push((prefix.clone() + &text, next_i, next_j));
+                }
+            }
+            None
+        })
     }
-}
 
-impl Record {
-    fn possible_arrangement_count(&self) -> usize {
-        num_arrangements('.', self.rec.to_string(), self.check.to_vec())
+    // The arrangement at position `k` of `arrangements`, without generating
+    // the ones before it.
+    fn nth_arrangement(&self, mut k: usize) -> Option<String> {
+        let ways = self.arrangement_table();
+        if k >= ways[0][0] {
+            return None;
+        }
+
+        let (mut i, mut j) = (0, 0);
+        let mut arrangement = String::new();
+        while i < self.rec.len() {
+            for (text, next_i, next_j) in self.choices(&ways, i, j) {
+                if k < ways[next_i][next_j] {
+                    arrangement += &text;
+                    (i, j) = (next_i, next_j);
+                    break;
+                }
+                k -= ways[next_i][next_j];
+            }
+        }
+        Some(arrangement)
     }
 
-    fn unfold(&mut self) {
-        self.rec = format!(
-            "{}?{}?{}?{}?{}",
-            self.rec, self.rec, self.rec, self.rec, self.rec
-        );
-        let mut new_check = vec![];
-        new_check.extend(self.check.clone());
-        new_check.extend(self.check.clone());
-        new_check.extend(self.check.clone());
-        new_check.extend(self.check.clone());
-        new_check.extend(self.check.clone());
-        self.check = new_check;
+    fn unfold(&mut self, factor: usize) {
+        self.rec = iter::repeat_n(self.rec.as_str(), factor).join("?");
+        self.check = self.check.repeat(factor);
     }
 }
 
@@ -108,7 +166,7 @@ fn part2() {
     let input = include_str!("../../puzzle_input/d12").trim();
     let mut records = Records::from(input);
     for r in records.data.iter_mut() {
-        r.unfold();
+        r.unfold(5);
     }
 
     println!(
@@ -120,3 +178,38 @@ fn part2() {
             .sum::<usize>()
     );
 }
+
+// Usage: RECORD [UNFOLD] [SAMPLES]
+//
+// Show how a single record (numbered from 1) can be arranged. If there are
+// more than SAMPLES arrangements, show that many spread evenly through the
+// full list instead.
+fn inspect(args: &[String]) {
+    let arg = |i: usize, default: Option<usize>| -> usize {
+        args.get(i)
+            .map(|a| a.parse().expect("Expected a number"))
+            .or(default)
+            .expect("Usage: RECORD [UNFOLD] [SAMPLES]")
+    };
+    let (index, factor, samples) = (arg(0, None), arg(1, Some(1)), arg(2, Some(10)));
+
+    let input = include_str!("../../puzzle_input/d12").trim();
+    let mut records = Records::from(input);
+    let record = &mut records.data[index - 1];
+    record.unfold(factor);
+
+    let count = record.possible_arrangement_count();
+    println!("{} {:?}", record.rec, record.check);
+    println!("{} possible arrangements", count);
+
+    if count <= samples {
+        for arrangement in record.arrangements() {
+            println!("{}", arrangement);
+        }
+    } else {
+        for s in 0..samples {
+            let k = s * (count - 1) / (samples - 1).max(1);
+            println!("#{}: {}", k, record.nth_arrangement(k).unwrap());
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use crate::grid::GridBitSet;
 
 pub fn run(part: u8) {
     match part {
@@ -11,18 +11,20 @@ pub fn run(part: u8) {
 struct Contraption {
     board: Vec<Vec<char>>,
     beams: Vec<Beam>,
-    energised: HashSet<Position>,
-    seen_beams: HashSet<Beam>,
+    energised: GridBitSet,
+    // one set of beam positions per direction
+    seen_beams: Vec<GridBitSet>,
 }
 
 impl From<&str> for Contraption {
     fn from(value: &str) -> Self {
-        let data = value.trim().lines().map(|l| l.chars().collect()).collect();
+        let data: Vec<Vec<char>> = value.trim().lines().map(|l| l.chars().collect()).collect();
+        let (height, width) = (data.len(), data[0].len());
         Self {
             board: data,
             beams: vec![Beam::default()],
-            energised: HashSet::new(),
-            seen_beams: HashSet::new(),
+            energised: GridBitSet::new(height, width),
+            seen_beams: vec![GridBitSet::new(height, width); 4],
         }
     }
 }
@@ -38,14 +40,15 @@ impl Contraption {
 
     fn reset(&mut self, start_beam: Beam) {
         self.beams = vec![start_beam];
-        self.energised = HashSet::new();
-        self.seen_beams = HashSet::new();
+        self.energised.clear();
+        self.seen_beams.iter_mut().for_each(GridBitSet::clear);
     }
 
     fn step(&mut self) {
         // energise
         for beam in self.beams.iter() {
-            self.energised.insert(beam.pos);
+            self.energised
+                .insert((beam.pos.0 as usize, beam.pos.1 as usize));
         }
 
         // split/bounce beams
@@ -57,9 +60,9 @@ impl Contraption {
                 let mut new_beam = *beam;
                 new_beam.dir = new_beam.dir.rotate_90_anticlockwise();
                 beam.dir = beam.dir.rotate_90_clockwise();
-                if !self.seen_beams.contains(&new_beam) {
+                let (row, col) = (row as usize, col as usize);
+                if self.seen_beams[new_beam.dir as usize].insert((row, col)) {
                     new_beams.push(new_beam);
-                    self.seen_beams.insert(new_beam);
                 }
             } else if let Some(dir) = beam.get_bounce(tile) {
                 beam.dir = dir;
@@ -1,15 +1,71 @@
-pub fn run(part: u8) {
+use std::collections::{BTreeMap, BTreeSet};
+
+use itertools::Itertools;
+
+pub fn run(part: u8, args: &[String]) {
     match part {
         1 => part1(),
         2 => part2(),
+        3 => part3(args),
         _ => println!("Part {} not implemented", part),
     }
 }
 
-struct Cubes {
-    blue: u32,
-    red: u32,
-    green: u32,
+// How many cubes there are of each colour. Colours that aren't there count
+// as 0.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Cubes(BTreeMap<String, u32>);
+
+impl Cubes {
+    // e.g. "12 red, 13 green, 14 blue"
+    fn parse(s: &str) -> Self {
+        let mut cubes = Cubes::default();
+        for cube in s.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+            let (num, colour) = cube.split_once(' ').unwrap();
+            let num: u32 = num.parse().unwrap();
+            *cubes.0.entry(colour.trim().to_string()).or_insert(0) += num;
+        }
+        cubes
+    }
+
+    fn get(&self, colour: &str) -> u32 {
+        self.0.get(colour).copied().unwrap_or(0)
+    }
+
+    fn fits_in(&self, bag: &Cubes) -> bool {
+        self.0.iter().all(|(colour, &num)| num <= bag.get(colour))
+    }
+
+    // The fewest cubes of each colour that both `self` and `other` fit in.
+    fn max(&self, other: &Cubes) -> Cubes {
+        let mut cubes = self.clone();
+        for (colour, &num) in &other.0 {
+            let entry = cubes.0.entry(colour.clone()).or_insert(0);
+            *entry = (*entry).max(num);
+        }
+        cubes
+    }
+
+    fn total(&self) -> u32 {
+        self.0.values().sum()
+    }
+
+    // The product of the number of cubes of each of the bag's colours, so a
+    // colour that never came up makes it 0.
+    fn power(&self, bag: &Cubes) -> u32 {
+        bag.0.keys().map(|colour| self.get(colour)).product()
+    }
+}
+
+impl std::fmt::Display for Cubes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cubes = self
+            .0
+            .iter()
+            .map(|(colour, num)| format!("{} {}", num, colour))
+            .join(", ");
+        write!(f, "{}", cubes)
+    }
 }
 
 struct Game {
@@ -18,54 +74,23 @@ struct Game {
 }
 
 impl Game {
-    fn is_possible(&self) -> bool {
-        self.rounds.iter().map(|r| r.red).max().unwrap() <= 12
-            && self.rounds.iter().map(|r| r.green).max().unwrap() <= 13
-            && self.rounds.iter().map(|r| r.blue).max().unwrap() <= 14
+    fn is_possible(&self, bag: &Cubes) -> bool {
+        self.rounds.iter().all(|r| r.fits_in(bag))
     }
 
     fn min_cubes(&self) -> Cubes {
-        Cubes {
-            red: self.rounds.iter().map(|r| r.red).max().unwrap(),
-            green: self.rounds.iter().map(|r| r.green).max().unwrap(),
-            blue: self.rounds.iter().map(|r| r.blue).max().unwrap(),
-        }
+        self.rounds
+            .iter()
+            .fold(Cubes::default(), |bag, r| bag.max(r))
     }
 }
 
 fn parse_game(line: &str) -> Game {
-    let mut game = Game {
-        id: line
-            .split(':')
-            .next()
-            .and_then(|s| s.split(' ').last())
-            .and_then(|s| s.parse().ok())
-            .unwrap(),
-        rounds: Vec::new(),
-    };
-
-    for round in line.split(':').last().unwrap().split(';') {
-        let mut cubes = Cubes {
-            blue: 0,
-            red: 0,
-            green: 0,
-        };
-
-        for cube in round.trim().split(',') {
-            let (num, colour) = cube.trim().split_once(' ').unwrap();
-            let num = num.parse().unwrap();
-
-            match colour {
-                "red" => cubes.red = num,
-                "green" => cubes.green = num,
-                "blue" => cubes.blue = num,
-                _ => panic!("unknown colour"),
-            }
-        }
-        game.rounds.push(cubes);
+    let (id, rounds) = line.split_once(':').unwrap();
+    Game {
+        id: id.split_whitespace().last().unwrap().parse().unwrap(),
+        rounds: rounds.split(';').map(Cubes::parse).collect(),
     }
-
-    game
 }
 
 fn get_games() -> Vec<Game> {
@@ -73,22 +98,140 @@ fn get_games() -> Vec<Game> {
     input.lines().map(|l: &str| parse_game(l)).collect()
 }
 
+// The bag with the fewest cubes in total that at least `n` of the games are
+// possible with, if there are that many games. Each colour only needs to be
+// tried at the amounts some game needs, and once all but one colour are fixed
+// the last one is just the nth smallest need among the games that fit.
+fn smallest_bag(games: &[Game], n: usize) -> Option<Cubes> {
+    if n > games.len() {
+        return None;
+    }
+    let needs: Vec<Cubes> = games.iter().map(Game::min_cubes).collect();
+    let colours: Vec<&String> = needs
+        .iter()
+        .flat_map(|c| c.0.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let Some((last, rest)) = colours.split_last().filter(|_| n > 0) else {
+        return Some(Cubes::default());
+    };
+
+    let choices: Vec<Vec<u32>> = if rest.is_empty() {
+        vec![vec![]]
+    } else {
+        rest.iter()
+            .map(|colour| {
+                needs
+                    .iter()
+                    .map(|c| c.get(colour))
+                    .sorted()
+                    .dedup()
+                    .collect_vec()
+            })
+            .multi_cartesian_product()
+            .collect()
+    };
+
+    choices
+        .into_iter()
+        .filter_map(|amounts| {
+            let mut bag = Cubes(
+                rest.iter()
+                    .map(|&colour| colour.clone())
+                    .zip(amounts)
+                    .collect(),
+            );
+            let fitting = needs
+                .iter()
+                .filter(|c| rest.iter().all(|colour| c.get(colour) <= bag.get(colour)))
+                .map(|c| c.get(last))
+                .sorted()
+                .collect_vec();
+            let amount = *fitting.get(n - 1)?;
+            bag.0.insert(last.to_string(), amount);
+            Some(bag)
+        })
+        .min_by_key(|bag| (bag.total(), bag.0.values().copied().collect_vec()))
+}
+
+fn puzzle_bag() -> Cubes {
+    Cubes::parse("12 red, 13 green, 14 blue")
+}
+
 fn part1() {
     let games = get_games();
-    let valid_game_ids =
-        games
-            .into_iter()
-            .filter_map(|g| if g.is_possible() { Some(g.id) } else { None });
+    let bag = puzzle_bag();
+    let valid_game_ids = games.into_iter().filter_map(|g| {
+        if g.is_possible(&bag) {
+            Some(g.id)
+        } else {
+            None
+        }
+    });
 
     println!("{}", valid_game_ids.sum::<u32>());
 }
 
 fn part2() {
     let games = get_games();
-    let powers = games
-        .into_iter()
-        .map(|g| g.min_cubes())
-        .map(|cubes| cubes.red * cubes.green * cubes.blue);
+    let bag = puzzle_bag();
+    let powers = games.into_iter().map(|g| g.min_cubes().power(&bag));
 
     println!("{}", powers.sum::<u32>());
 }
+
+// Usage: [--bag "N COLOUR, ..."] [--at-least N]
+//
+// Show each game's smallest bag and its power, and whether it's possible
+// with the given bag (part 1's by default). With --at-least, also find the
+// smallest bag that makes at least N games possible.
+fn part3(args: &[String]) {
+    let mut bag = puzzle_bag();
+    let mut at_least = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().expect("Missing value for argument");
+        match arg.as_str() {
+            "--bag" => bag = Cubes::parse(value()),
+            "--at-least" => at_least = Some(value().parse().expect("Expected a number")),
+            _ => panic!("Unknown argument '{}'", arg),
+        }
+    }
+
+    let games = get_games();
+    println!("Bag: {}", bag);
+    for game in &games {
+        let min_cubes = game.min_cubes();
+        println!(
+            "Game {:>3}  {:<8}  power {:>5}  needs {}",
+            game.id,
+            if game.is_possible(&bag) {
+                "possible"
+            } else {
+                "-"
+            },
+            min_cubes.power(&bag),
+            min_cubes
+        );
+    }
+
+    let possible = games.iter().filter(|g| g.is_possible(&bag));
+    println!(
+        "Sum of possible IDs: {}",
+        possible.map(|g| g.id).sum::<u32>()
+    );
+
+    if let Some(n) = at_least {
+        match smallest_bag(&games, n) {
+            Some(bag) => println!(
+                "Smallest bag for {} games: {} ({} cubes)",
+                n,
+                bag,
+                bag.total()
+            ),
+            None => println!("There aren't {} games", n),
+        }
+    }
+}
@@ -1,85 +1,19 @@
 use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::ops::Range;
-use std::{
-    collections::{HashMap, HashSet},
-    usize,
-};
 
-pub fn run(part: u8) {
+use crate::grid::Position;
+
+pub fn run(part: u8, args: &[String]) {
     match part {
         1 => part1(),
         2 => part2(),
+        3 => part3(args),
         _ => println!("Part {} not implemented", part),
     }
 }
 
-// PART 1
-type Position = (usize, usize);
-
-trait Neighbours {
-    fn has_neighbour(&self, pos: Position) -> bool;
-}
-
-impl Neighbours for HashSet<Position> {
-    fn has_neighbour(&self, pos: Position) -> bool {
-        let (row, col) = pos;
-        let row = row as i32;
-        let col = col as i32;
-        for r in row - 1..=row + 1 {
-            for c in col - 1..=col + 1 {
-                if self.contains(&(r as usize, c as usize)) {
-                    return true;
-                }
-            }
-        }
-        false
-    }
-}
-
-fn get_part_numbers(symbol_positions: HashSet<(usize, usize)>) -> Vec<u32> {
-    let mut result = Vec::new();
-    let input = include_str!("../../puzzle_input/d3").trim();
-
-    let re = Regex::new(r"\d+").unwrap();
-    for (row, line) in input.lines().enumerate() {
-        for m in re.find_iter(line) {
-            let num = m.as_str().parse().unwrap();
-            for i in m.range() {
-                if symbol_positions.has_neighbour((row, i)) {
-                    result.push(num);
-                    break;
-                }
-            }
-        }
-    }
-    result
-}
-
-fn get_symbols() -> HashSet<Position> {
-    let mut result = HashSet::new();
-
-    let input = include_str!("../../puzzle_input/d3").trim();
-
-    let re = Regex::new(r"[^.\d]").unwrap();
-    for (row, line) in input.lines().enumerate() {
-        for m in re.find_iter(line) {
-            for i in m.range() {
-                result.insert((row, i));
-            }
-        }
-    }
-    result
-}
-
-fn part1() {
-    let symbol_positions = get_symbols();
-    let part_number_sum: u32 = get_part_numbers(symbol_positions).into_iter().sum();
-
-    println!("{}", part_number_sum);
-}
-
-// PART 2
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum NodeData {
     Number(u32),
     Symbol(char),
@@ -87,23 +21,34 @@ enum NodeData {
 
 struct Vertex {
     data: NodeData,
-    positions: (usize, Range<usize>),
+    row: usize,
+    cols: Range<usize>,
 }
 
-struct Graph {
+impl Vertex {
+    // Every position touching this one, including diagonally, that's on the
+    // grid. Positions past the bottom or right edge just won't have anything
+    // in them.
+    fn surroundings(&self) -> impl Iterator<Item = Position> + '_ {
+        let rows = self.row.saturating_sub(1)..=self.row + 1;
+        let cols = self.cols.start.saturating_sub(1)..=self.cols.end;
+        rows.flat_map(move |r| cols.clone().map(move |c| (r, c)))
+    }
+}
+
+// The numbers and symbols in a schematic, with an edge between each symbol
+// and every number next to it.
+struct Schematic {
     vertices: Vec<Vertex>,
-    vertex_lookup: HashMap<Position, usize>,
+    neighbours: Vec<BTreeSet<usize>>,
 }
 
-impl From<&str> for Graph {
+impl From<&str> for Schematic {
     fn from(value: &str) -> Self {
-        let mut result = Self {
-            vertices: Vec::default(),
-            vertex_lookup: HashMap::default(),
-        };
-
-        let re = Regex::new(r"\d+|[^.]").unwrap();
+        let mut vertices = vec![];
+        let mut vertex_lookup: HashMap<Position, usize> = HashMap::new();
 
+        let re = Regex::new(r"\d+|[^.\d]").unwrap();
         for (row, line) in value.lines().enumerate() {
             for m in re.find_iter(line) {
                 let s = m.as_str();
@@ -114,54 +59,151 @@ impl From<&str> for Graph {
                 };
 
                 for col in m.range() {
-                    result
-                        .vertex_lookup
-                        .insert((row, col), result.vertices.len());
+                    vertex_lookup.insert((row, col), vertices.len());
                 }
-
-                result.vertices.push(Vertex {
+                vertices.push(Vertex {
                     data,
-                    positions: (row, m.range()),
+                    row,
+                    cols: m.range(),
                 });
             }
         }
-        result
-    }
-}
 
-fn part2() {
-    let input = include_str!("../../puzzle_input/d3").trim();
-    let graph = Graph::from(input);
-
-    // find gears
-    let mut total = 0u32;
-    for vertex in graph.vertices.iter() {
-        if matches!(vertex.data, NodeData::Symbol('*')) {
-            // search around gear symbol '*' for neighbours
-            let mut neighbour_vertices = HashSet::new();
-            let (row, col) = (vertex.positions.0, vertex.positions.1.start);
-            for r in row - 1..=row + 1 {
-                for c in col - 1..=col + 1 {
-                    if let Some(&node) = graph.vertex_lookup.get(&(r, c)) {
-                        neighbour_vertices.insert(node);
+        let mut neighbours = vec![BTreeSet::new(); vertices.len()];
+        for (i, vertex) in vertices.iter().enumerate() {
+            if !matches!(vertex.data, NodeData::Symbol(_)) {
+                continue;
+            }
+            for pos in vertex.surroundings() {
+                if let Some(&j) = vertex_lookup.get(&pos) {
+                    if matches!(vertices[j].data, NodeData::Number(_)) {
+                        neighbours[i].insert(j);
+                        neighbours[j].insert(i);
                     }
                 }
             }
+        }
 
-            // pick out numbers around gear '*' symbol
-            let nums: Vec<_> = neighbour_vertices
-                .into_iter()
-                .filter_map(|node| match graph.vertices[node].data {
-                    NodeData::Number(num) => Some(num),
-                    _ => None,
-                })
-                .collect();
-
-            if nums.len() == 2 {
-                // exactly 2 numeric neighbours, so it's a gear
-                total += nums[0] * nums[1];
-            }
+        Self {
+            vertices,
+            neighbours,
+        }
+    }
+}
+
+impl Schematic {
+    fn number(&self, i: usize) -> Option<u32> {
+        match self.vertices[i].data {
+            NodeData::Number(num) => Some(num),
+            NodeData::Symbol(_) => None,
+        }
+    }
+
+    fn symbol(&self, i: usize) -> Option<char> {
+        match self.vertices[i].data {
+            NodeData::Symbol(c) => Some(c),
+            NodeData::Number(_) => None,
+        }
+    }
+
+    // The numbers next to at least one symbol.
+    fn part_numbers(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.vertices.len())
+            .filter(|&i| !self.neighbours[i].is_empty())
+            .filter_map(|i| self.number(i))
+    }
+
+    // The numbers next to no symbols at all.
+    fn loose_numbers(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.vertices.len())
+            .filter(|&i| self.neighbours[i].is_empty())
+            .filter_map(|i| self.number(i))
+    }
+
+    // Each symbol with exactly `n` numbers next to it, and those numbers.
+    fn symbols_with(&self, n: usize) -> impl Iterator<Item = (char, Vec<u32>)> + '_ {
+        (0..self.vertices.len())
+            .filter(move |&i| self.neighbours[i].len() == n)
+            .filter_map(|i| {
+                let nums = self.neighbours[i].iter().filter_map(|&j| self.number(j));
+                Some((self.symbol(i)?, nums.collect()))
+            })
+    }
+
+    // For each type of symbol, the sum of the products of the numbers next to
+    // the ones with exactly `n` numbers. Gear ratios are the '*' entry for 2.
+    fn products_by_symbol(&self, n: usize) -> BTreeMap<char, u64> {
+        let mut totals = BTreeMap::new();
+        for (symbol, nums) in self.symbols_with(n) {
+            let product: u64 = nums.into_iter().map(u64::from).product();
+            *totals.entry(symbol).or_insert(0) += product;
         }
+        totals
     }
-    println!("{}", total);
+}
+
+fn get_schematic() -> Schematic {
+    let input = include_str!("../../puzzle_input/d3").trim();
+    Schematic::from(input)
+}
+
+fn part1() {
+    let part_number_sum: u32 = get_schematic().part_numbers().sum();
+
+    println!("{}", part_number_sum);
+}
+
+fn part2() {
+    let gear_ratios = get_schematic().products_by_symbol(2);
+
+    println!("{}", gear_ratios.get(&'*').copied().unwrap_or(0));
+}
+
+// Usage: [COUNT]
+//
+// For each type of symbol, how many numbers the symbols have next to them,
+// and the sum of the products for the ones with exactly COUNT (2 by default).
+fn part3(args: &[String]) {
+    let count: usize = match args {
+        [] => 2,
+        [count] => count.parse().expect("Expected a number"),
+        _ => panic!("Unknown arguments {:?}", args),
+    };
+
+    let schematic = get_schematic();
+    let mut by_count: BTreeMap<char, BTreeMap<usize, usize>> = BTreeMap::new();
+    for i in 0..schematic.vertices.len() {
+        if let Some(symbol) = schematic.symbol(i) {
+            let num_numbers = schematic.neighbours[i].len();
+            *by_count
+                .entry(symbol)
+                .or_default()
+                .entry(num_numbers)
+                .or_insert(0) += 1;
+        }
+    }
+
+    let products = schematic.products_by_symbol(count);
+    for (symbol, counts) in by_count {
+        let counts: Vec<String> = counts
+            .into_iter()
+            .map(|(n, symbols)| format!("{}x{}", symbols, n))
+            .collect();
+        println!(
+            "{}  numbers next to each: {}  products for {}: {}",
+            symbol,
+            counts.join(" "),
+            count,
+            products.get(&symbol).copied().unwrap_or(0)
+        );
+    }
+
+    let part_numbers: Vec<u32> = schematic.part_numbers().collect();
+    let loose_numbers: Vec<u32> = schematic.loose_numbers().collect();
+    println!(
+        "{} part numbers adding up to {}",
+        part_numbers.len(),
+        part_numbers.iter().sum::<u32>()
+    );
+    println!("{} numbers not next to a symbol", loose_numbers.len());
 }
@@ -1,184 +1,185 @@
-use std::cmp::Ordering;
 use std::collections::HashMap;
 
-pub fn run(part: u8) {
+pub fn run(part: u8, args: &[String]) {
     match part {
         1 => part1(),
         2 => part2(),
+        3 => part3(args),
         _ => println!("Part {} not implemented", part),
     }
 }
 
 type Bid = u32;
 
-lazy_static! {
-    static ref CARDRANK: HashMap<char, u32> = {
-        let mut m = HashMap::new();
-        m.insert('?', 0);   // denotes joker
-        m.insert('2', 1);
-        m.insert('3', 2);
-        m.insert('4', 3);
-        m.insert('5', 4);
-        m.insert('6', 5);
-        m.insert('7', 6);
-        m.insert('8', 7);
-        m.insert('9', 8);
-        m.insert('T', 9);
-        m.insert('J', 10);
-        m.insert('Q', 11);
-        m.insert('K', 12);
-        m.insert('A', 13);
-        m
-    };
+// How a game of Camel Cards is played.
+#[derive(Clone, Debug)]
+struct RuleSet {
+    // Every card, from weakest to strongest.
+    card_order: Vec<char>,
+    // Cards that stand in for whatever makes the hand strongest. They still
+    // rank by `card_order` when breaking ties.
+    wildcards: Vec<char>,
+    hand_size: usize,
 }
 
-#[derive(Clone, Eq, PartialEq)]
-struct Hand {
-    cards: String,
-}
-
-impl Hand {
-    fn replace_jokers(&self) -> Hand {
-        let num_jokers = self.cards.chars().filter(|&c| c == '?').count();
-
-        if num_jokers == 0 {
-            Hand {
-                cards: self.cards.clone(),
-            }
-        } else if num_jokers == 5 {
-            Hand {
-                cards: "AAAAA".to_string(),
-            }
-        } else {
-            let counts = self.get_card_counts();
-            let mut nonjoker_counts: Vec<_> =
-                counts.iter().filter(|(&card, _)| card != '?').collect();
-            nonjoker_counts.sort_by_key(|(_, &count)| -(count as i32));
-
-            let (&most_common_nonjoker, _) = nonjoker_counts[0];
-            Hand {
-                cards: self.cards.replace('?', &most_common_nonjoker.to_string()),
-            }
+impl RuleSet {
+    fn standard() -> Self {
+        Self {
+            card_order: "23456789TJQKA".chars().collect(),
+            wildcards: vec![],
+            hand_size: 5,
         }
     }
 
-    fn get_card_counts(&self) -> HashMap<char, usize> {
-        let mut counts = HashMap::new();
-        for card in self.cards.chars() {
-            *counts.entry(card).or_insert(0) += 1;
+    fn jokers() -> Self {
+        Self {
+            card_order: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J'],
+            hand_size: 5,
         }
-        counts
-    }
-
-    fn has_five_of_kind(&self) -> bool {
-        let counts = self.get_card_counts();
-        counts.values().filter(|&&v| v == 5).count() > 0
     }
 
-    fn has_four_of_a_kind(&self) -> bool {
-        let counts = self.get_card_counts();
-        counts.values().filter(|&&v| v == 4).count() > 0
+    fn strength(&self, card: char) -> usize {
+        self.card_order
+            .iter()
+            .position(|&c| c == card)
+            .unwrap_or_else(|| panic!("Unknown card '{}'", card))
     }
+}
 
-    fn has_three_of_a_kind(&self) -> bool {
-        let counts = self.get_card_counts();
-        counts.values().filter(|&&v| v == 3).count() > 0
-    }
+// Hands compare by their signature first, then card by card.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Hand {
+    // How many of each card there are, most first, after the wildcards have
+    // joined the biggest group. Comparing these puts the hand types in the
+    // right order, e.g. [3, 2] (full house) beats [3, 1, 1] (three of a kind).
+    signature: Vec<usize>,
+    strengths: Vec<usize>,
+    cards: String,
+}
 
-    fn has_pair(&self) -> bool {
-        let counts = self.get_card_counts();
-        counts.values().filter(|&&v| v == 2).count() > 0
-    }
+impl Hand {
+    fn new(cards: &str, rules: &RuleSet) -> Self {
+        if cards.chars().count() != rules.hand_size {
+            panic!("Hand '{}' should have {} cards", cards, rules.hand_size);
+        }
 
-    fn is_full_house(&self) -> bool {
-        self.has_three_of_a_kind() && self.has_pair()
-    }
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        let mut num_wild = 0;
+        for card in cards.chars() {
+            if rules.wildcards.contains(&card) {
+                num_wild += 1;
+            } else {
+                *counts.entry(card).or_insert(0) += 1;
+            }
+        }
 
-    fn has_two_pair(&self) -> bool {
-        let counts = self.get_card_counts();
-        counts.values().filter(|&&v| v == 2).count() == 2
-    }
+        let mut signature: Vec<usize> = counts.into_values().collect();
+        signature.sort_by(|a, b| b.cmp(a));
+        match signature.first_mut() {
+            Some(biggest) => *biggest += num_wild,
+            None => signature.push(num_wild),
+        }
 
-    pub fn rank(&self) -> u32 {
-        let best_hand = self.replace_jokers();
-
-        if best_hand.has_five_of_kind() {
-            7
-        } else if best_hand.has_four_of_a_kind() {
-            6
-        } else if best_hand.is_full_house() {
-            5
-        } else if best_hand.has_three_of_a_kind() {
-            4
-        } else if best_hand.has_two_pair() {
-            3
-        } else if best_hand.has_pair() {
-            2
-        } else {
-            1
+        Self {
+            signature,
+            strengths: cards.chars().map(|c| rules.strength(c)).collect(),
+            cards: cards.to_string(),
         }
     }
-}
 
-impl Ord for Hand {
-    fn cmp(&self, other: &Self) -> Ordering {
-        let (r1, r2) = (self.rank(), other.rank());
-        if r1 != r2 {
-            return r1.cmp(&r2);
+    // The usual names for five card hands. Other sizes say how many groups
+    // of each size there are, e.g. "2 triples" or "4 of a kind and a pair".
+    fn kind(&self) -> String {
+        match self.signature.as_slice() {
+            [5] => return "five of a kind".to_string(),
+            [4, 1] => return "four of a kind".to_string(),
+            [3, 2] => return "full house".to_string(),
+            [3, 1, 1] => return "three of a kind".to_string(),
+            [2, 2, 1] => return "two pair".to_string(),
+            [2, 1, 1, 1] => return "one pair".to_string(),
+            [1, 1, 1, 1, 1] => return "high card".to_string(),
+            _ => {}
         }
 
-        for (c1, c2) in self.cards.chars().zip(other.cards.chars()) {
-            if c1 != c2 {
-                return CARDRANK.get(&c1).cmp(&CARDRANK.get(&c2));
-            }
+        let mut groups = vec![];
+        for size in (2..=self.signature[0]).rev() {
+            let count = self.signature.iter().filter(|&&n| n == size).count();
+            let name = match (size, count) {
+                (_, 0) => continue,
+                (2, 1) => "a pair".to_string(),
+                (2, _) => format!("{} pairs", count),
+                (3, 1) => "a triple".to_string(),
+                (3, _) => format!("{} triples", count),
+                (_, 1) => format!("{} of a kind", size),
+                (_, _) => format!("{} lots of {} of a kind", count, size),
+            };
+            groups.push(name);
+        }
+        if groups.is_empty() {
+            "high card".to_string()
+        } else {
+            groups.join(" and ")
         }
-
-        std::cmp::Ordering::Equal
-    }
-}
-
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
     }
 }
 
-fn parse_hands() -> Vec<(Hand, Bid)> {
+fn parse_hands(rules: &RuleSet) -> Vec<(Hand, Bid)> {
     let input = include_str!("../../puzzle_input/d7").trim();
     let hands = input.lines().map(|l| l.split_once(' ').unwrap());
     hands
-        .map(|(h, b)| {
-            (
-                Hand {
-                    cards: h.to_string(),
-                },
-                b.parse::<u32>().unwrap(),
-            )
-        })
+        .map(|(h, b)| (Hand::new(h, rules), b.parse::<u32>().unwrap()))
         .collect()
 }
 
-fn part1() {
-    let mut hands = parse_hands();
-    hands.sort_by_key(|(h, _)| h.clone());
+// Hands from weakest to strongest.
+fn ranked_hands(rules: &RuleSet) -> Vec<(Hand, Bid)> {
+    let mut hands = parse_hands(rules);
+    hands.sort();
+    hands
+}
 
-    let mut score = 0;
-    for (rank, bid) in hands.into_iter().map(|(_, b)| b).enumerate() {
-        score += (rank + 1) as u32 * bid;
-    }
-    println!("{}", score);
+fn total_winnings(hands: &[(Hand, Bid)]) -> u32 {
+    hands
+        .iter()
+        .enumerate()
+        .map(|(rank, (_, bid))| (rank + 1) as u32 * bid)
+        .sum()
+}
+
+fn part1() {
+    println!("{}", total_winnings(&ranked_hands(&RuleSet::standard())));
 }
 
 fn part2() {
-    let mut hands = parse_hands();
-    for (hand, _) in hands.iter_mut() {
-        hand.cards = hand.cards.replace('J', &'?'.to_string());
+    println!("{}", total_winnings(&ranked_hands(&RuleSet::jokers())));
+}
+
+// Usage: [--order CARDS] [--wild CARDS] [--size HAND_SIZE]
+//
+// Rank the hands under different rules, starting from the part 1 rules.
+fn part3(args: &[String]) {
+    let mut rules = RuleSet::standard();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().expect("Missing value for argument");
+        match arg.as_str() {
+            "--order" => rules.card_order = value().chars().collect(),
+            "--wild" => rules.wildcards = value().chars().collect(),
+            "--size" => rules.hand_size = value().parse().expect("Expected a number"),
+            _ => panic!("Unknown argument '{}'", arg),
+        }
     }
-    hands.sort_by_key(|(h, _)| h.clone());
 
-    let mut score = 0;
-    for (rank, bid) in hands.into_iter().map(|(_, b)| b).enumerate() {
-        score += (rank + 1) as u32 * bid;
+    let hands = ranked_hands(&rules);
+    for (rank, (hand, bid)) in hands.iter().enumerate() {
+        println!(
+            "{:>5}  {}  {:>4}  {}",
+            rank + 1,
+            hand.cards,
+            bid,
+            hand.kind()
+        );
     }
-    println!("{}", score);
+    println!("Total winnings: {}", total_winnings(&hands));
 }
@@ -1,6 +1,3 @@
-#[macro_use]
-extern crate lazy_static;
-
 mod day1;
 mod day10;
 mod day11;
@@ -24,6 +21,7 @@ mod day7;
 mod day8;
 mod day9;
 
+use aoc_common::{grid, polygon};
 use clap::Parser;
 
 #[derive(Parser)]
@@ -34,24 +32,28 @@ struct Cli {
 
     #[arg(short, long, value_name = "PART")]
     part: u8,
+
+    // Extra arguments for the days that take them, after a `--`
+    #[arg(last = true)]
+    args: Vec<String>,
 }
 
 fn main() {
     let cli = Cli::parse();
 
     match cli.day {
-        1 => day1::run(cli.part),
-        2 => day2::run(cli.part),
-        3 => day3::run(cli.part),
+        1 => day1::run(cli.part, &cli.args),
+        2 => day2::run(cli.part, &cli.args),
+        3 => day3::run(cli.part, &cli.args),
         4 => day4::run(cli.part),
         5 => day5::run(cli.part),
         6 => day6::run(cli.part),
-        7 => day7::run(cli.part),
+        7 => day7::run(cli.part, &cli.args),
         8 => day8::run(cli.part),
         9 => day9::run(cli.part),
         10 => day10::run(cli.part),
         11 => day11::run(cli.part),
-        12 => day12::run(cli.part),
+        12 => day12::run(cli.part, &cli.args),
         13 => day13::run(cli.part),
         14 => day14::run(cli.part),
         15 => day15::run(cli.part),
@@ -1,50 +1,152 @@
-use std::iter::zip;
+use std::{collections::BTreeSet, iter::zip};
 
 use aoc_rust_2024::{helpers::make_counter, io};
+use itertools::Itertools;
 
-fn parse_input() -> Vec<(u32, u32)> {
-    let input = io::get_puzzle_input(1);
-    let input = input.trim();
-
-    input
-        .lines()
-        .map(|line| {
-            let (id1, id2) = line.split_once(' ').unwrap();
-            (id1.parse().unwrap(), id2.trim().parse().unwrap())
-        })
-        .collect()
+// The two lists of location IDs, which don't have to be the same length.
+struct Lists {
+    left: Vec<u32>,
+    right: Vec<u32>,
 }
 
-fn part1_solution() -> u32 {
-    let lines = parse_input();
+impl Lists {
+    // Each line has a left ID, a right ID or both, separated by any amount of
+    // whitespace. A line with only one ID is in the right list if it's
+    // indented and the left list otherwise.
+    fn parse(input: &str) -> Self {
+        let mut lists = Self {
+            left: vec![],
+            right: vec![],
+        };
+        for line in input.lines().filter(|line| !line.trim().is_empty()) {
+            let ids: Vec<u32> = line
+                .split_whitespace()
+                .map(|id| id.parse().unwrap())
+                .collect();
+            match *ids.as_slice() {
+                [left, right] => {
+                    lists.left.push(left);
+                    lists.right.push(right);
+                }
+                [id] if line.starts_with(char::is_whitespace) => lists.right.push(id),
+                [id] => lists.left.push(id),
+                _ => panic!("Expected one or two IDs in '{}'", line),
+            }
+        }
+        lists
+    }
+
+    // Smallest with smallest, second smallest with second smallest and so
+    // on, as (left, right). IDs left over from the longer list aren't paired.
+    fn sorted_pairs(&self) -> Vec<(u32, u32)> {
+        zip(self.left.iter().sorted(), self.right.iter().sorted())
+            .map(|(&left, &right)| (left, right))
+            .collect()
+    }
+
+    fn unpaired(&self) -> (Vec<u32>, Vec<u32>) {
+        let num_pairs = self.left.len().min(self.right.len());
+        let rest = |list: &[u32]| list.iter().sorted().skip(num_pairs).copied().collect();
+        (rest(&self.left), rest(&self.right))
+    }
 
-    let mut list1: Vec<_> = lines.iter().map(|line| line.0).collect();
-    let mut list2: Vec<_> = lines.iter().map(|line| line.1).collect();
+    fn distance(&self) -> u32 {
+        self.sorted_pairs()
+            .into_iter()
+            .map(|(left, right)| left.abs_diff(right))
+            .sum()
+    }
+
+    fn similarity(&self) -> u32 {
+        let counter1 = make_counter(self.left.iter());
+        let counter2 = make_counter(self.right.iter());
+
+        counter1
+            .into_iter()
+            .map(|(id, count)| id * (counter2.get(&id).unwrap_or(&0) * count) as u32)
+            .sum()
+    }
+}
 
-    list1.sort();
-    list2.sort();
+fn parse_input() -> Lists {
+    let input = io::get_puzzle_input(1);
+    Lists::parse(&input)
+}
 
-    zip(list1, list2)
-        .map(|(id1, id2)| (id1 as i32 - id2 as i32).unsigned_abs())
-        .sum()
+fn part1_solution() -> u32 {
+    parse_input().distance()
 }
 
 fn part2_solution() -> u32 {
-    let lines = parse_input();
+    parse_input().similarity()
+}
+
+// Usage: report [TOP]
+//
+// For each ID, how many times it's in each list, and what it adds to the
+// distance (through the pairs it's the left side of) and the similarity
+// score. Then the TOP pairs that are furthest apart.
+fn print_report(args: &[String]) {
+    let top: usize = match args {
+        [] => 10,
+        [top] => top.parse().expect("Cannot parse TOP!"),
+        _ => panic!("Unknown arguments {:?}", args),
+    };
+
+    let lists = parse_input();
+    let left_counts = make_counter(lists.left.iter().copied());
+    let right_counts = make_counter(lists.right.iter().copied());
+    let pairs = lists.sorted_pairs();
+    let pair_distances = pairs
+        .iter()
+        .map(|&(left, right)| (left, left.abs_diff(right)))
+        .into_grouping_map()
+        .sum();
+
+    println!("      ID   Left  Right  Distance  Similarity");
+    let ids: BTreeSet<u32> = lists.left.iter().chain(&lists.right).copied().collect();
+    for id in ids {
+        let left = left_counts.get(&id).copied().unwrap_or(0);
+        let right = right_counts.get(&id).copied().unwrap_or(0);
+        println!(
+            "{:>8} {:>6} {:>6} {:>9} {:>11}",
+            id,
+            left,
+            right,
+            pair_distances.get(&id).copied().unwrap_or(0),
+            id * (left * right) as u32
+        );
+    }
 
-    let list1: Vec<_> = lines.iter().map(|line| line.0).collect();
-    let list2: Vec<_> = lines.iter().map(|line| line.1).collect();
+    println!();
+    println!("Furthest apart pairs:");
+    for (left, right) in pairs
+        .iter()
+        .sorted_by_key(|&&(left, right)| std::cmp::Reverse(left.abs_diff(right)))
+        .take(top)
+    {
+        println!("{:>8} {:>8} {:>8}", left, right, left.abs_diff(*right));
+    }
 
-    let counter1 = make_counter(list1.into_iter());
-    let counter2 = make_counter(list2.into_iter());
+    let (left, right) = lists.unpaired();
+    if !left.is_empty() || !right.is_empty() {
+        println!();
+        println!("Unpaired left: {:?}", left);
+        println!("Unpaired right: {:?}", right);
+    }
 
-    counter1
-        .into_iter()
-        .map(|(id, count)| id * (counter2.get(&id).unwrap_or(&0) * count) as u32)
-        .sum()
+    println!();
+    println!("Distance: {}", lists.distance());
+    println!("Similarity: {}", lists.similarity());
 }
 
 fn main() {
-    println!("{}", part1_solution());
-    println!("{}", part2_solution());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("report") => print_report(&args[1..]),
+        _ => {
+            println!("{}", part1_solution());
+            println!("{}", part2_solution());
+        }
+    }
 }
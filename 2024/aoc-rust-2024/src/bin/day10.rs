@@ -1,71 +1,158 @@
-use std::collections::HashSet;
-
 use itertools::Itertools;
 
-use aoc_rust_2024::io;
+use aoc_rust_2024::{
+    grid::{GridBitSet, GridMap, Position},
+    io,
+};
 
 type Map = Vec<Vec<u32>>;
 
-#[derive(PartialEq, Eq, Hash)]
-struct Position {
-    row: usize,
-    col: usize,
+const TRAILHEAD: u32 = 0;
+const SUMMIT: u32 = 9;
+
+fn get_neighbours((row, col): Position, map: &Map) -> Vec<Position> {
+    let height = map.len() as i32;
+    let width = map[0].len() as i32;
+
+    let nbhrs = [
+        (row as i32 - 1, col as i32),
+        (row as i32 + 1, col as i32),
+        (row as i32, col as i32 - 1),
+        (row as i32, col as i32 + 1),
+    ]
+    .into_iter()
+    .filter(|(row, col)| (0..height).contains(row) && (0..width).contains(col))
+    .map(|(row, col)| (row as usize, col as usize));
+    nbhrs.collect()
 }
 
-impl Position {
-    fn get_neighbours(&self, map: &Map) -> Vec<Self> {
-        let height = map.len() as i32;
-        let width = map[0].len() as i32;
-
-        let nbhrs = [
-            (self.row as i32 - 1, self.col as i32),
-            (self.row as i32 + 1, self.col as i32),
-            (self.row as i32, self.col as i32 - 1),
-            (self.row as i32, self.col as i32 + 1),
-        ]
+// The neighbours that a hiking trail can step up to from `pos`.
+fn get_uphill_neighbours(pos: Position, map: &Map) -> impl Iterator<Item = Position> + '_ {
+    let next_height = map[pos.0][pos.1] + 1;
+    get_neighbours(pos, map)
         .into_iter()
-        .filter(|(row, col)| (0..height).contains(row) && (0..width).contains(col))
-        .map(|(row, col)| Position {
-            row: row as usize,
-            col: col as usize,
-        });
-        nbhrs.collect()
-    }
+        .filter(move |&(row, col)| map[row][col] == next_height)
 }
 
-fn get_reachable_summits(map: &Map, position: Position) -> HashSet<Position> {
-    let current_height = map[position.row][position.col];
-    if current_height == 9 {
-        return HashSet::from([position]);
-    }
+struct Trailhead {
+    position: Position,
+    score: usize,
+    rating: usize,
+}
 
-    let nbrs = position.get_neighbours(map);
-    nbrs.into_iter()
-        .filter(|pos| map[pos.row][pos.col] == current_height + 1)
-        .flat_map(|pos| get_reachable_summits(map, pos).into_iter())
-        .collect()
+// Everything about the hiking trails on the map, worked out one height at a
+// time so that trails sharing cells share the work.
+struct TrailAnalysis {
+    // The summits reachable from each cell that's on some trail.
+    summits: GridMap<GridBitSet>,
+    // The number of trails from each cell to any summit.
+    ratings: GridMap<usize>,
+    // The number of trails from any trailhead to each cell.
+    approaches: GridMap<usize>,
 }
 
-fn get_rating(map: &Map, position: Position) -> usize {
-    let current_height = map[position.row][position.col];
-    if current_height == 9 {
-        return 1;
+impl TrailAnalysis {
+    fn new(map: &Map) -> Self {
+        let (height, width) = (map.len(), map[0].len());
+        let layers = (0..height)
+            .cartesian_product(0..width)
+            .into_group_map_by(|&(row, col)| map[row][col]);
+        let layer = |h: u32| layers.get(&h).into_iter().flatten().copied();
+
+        // Downhill from the summits, each cell takes the summits and ratings
+        // of the cells one step up from it.
+        let mut summits = GridMap::new(height, width);
+        let mut ratings = GridMap::new(height, width);
+        for pos in layer(SUMMIT) {
+            let mut summit = GridBitSet::new(height, width);
+            summit.insert(pos);
+            summits.insert(pos, summit);
+            ratings.insert(pos, 1);
+        }
+        for h in (TRAILHEAD..SUMMIT).rev() {
+            for pos in layer(h) {
+                let mut reachable = GridBitSet::new(height, width);
+                let mut rating = 0;
+                for next in get_uphill_neighbours(pos, map) {
+                    if let Some(next_summits) = summits.get(next) {
+                        reachable.union_with(next_summits);
+                        rating += ratings[next];
+                    }
+                }
+                if rating > 0 {
+                    summits.insert(pos, reachable);
+                    ratings.insert(pos, rating);
+                }
+            }
+        }
+
+        // Uphill from the trailheads, each cell passes on how many ways there
+        // are to reach it.
+        let mut approaches = GridMap::new(height, width);
+        for pos in layer(TRAILHEAD) {
+            approaches.insert(pos, 1);
+        }
+        for h in TRAILHEAD..SUMMIT {
+            for pos in layer(h) {
+                let Some(&count) = approaches.get(pos) else {
+                    continue;
+                };
+                for next in get_uphill_neighbours(pos, map) {
+                    *approaches.get_or_insert_with(next, || 0) += count;
+                }
+            }
+        }
+
+        Self {
+            summits,
+            ratings,
+            approaches,
+        }
     }
 
-    let nbrs = position.get_neighbours(map);
-    nbrs.into_iter()
-        .filter(|pos| map[pos.row][pos.col] == current_height + 1)
-        .map(|pos| get_rating(map, pos))
-        .sum()
-}
+    fn trailheads(&self, map: &Map) -> Vec<Trailhead> {
+        self.approaches
+            .keys()
+            .filter(|&(row, col)| map[row][col] == TRAILHEAD)
+            .map(|pos| Trailhead {
+                position: pos,
+                score: self.summits.get(pos).map_or(0, GridBitSet::len),
+                rating: self.ratings.get(pos).copied().unwrap_or(0),
+            })
+            .collect()
+    }
 
-fn iter_map_positions(map: &Map) -> impl Iterator<Item = Position> {
-    let height = map.len();
-    let width = map[0].len();
+    // The number of distinct trails that pass through `pos`.
+    fn popularity(&self, pos: Position) -> usize {
+        let approaches = self.approaches.get(pos).copied().unwrap_or(0);
+        let rating = self.ratings.get(pos).copied().unwrap_or(0);
+        approaches * rating
+    }
 
-    (0..height)
-        .cartesian_product(0..width)
-        .map(|(row, col)| Position { row, col })
+    // The map with only the `top` busiest cells shown.
+    fn render_popular(&self, map: &Map, top: usize) -> String {
+        let mut shown = GridBitSet::new(map.len(), map[0].len());
+        shown.extend(
+            self.ratings
+                .keys()
+                .filter(|&pos| self.popularity(pos) > 0)
+                .sorted_by_key(|&pos| std::cmp::Reverse(self.popularity(pos)))
+                .take(top),
+        );
+
+        map.iter()
+            .enumerate()
+            .map(|(row, line)| {
+                line.iter()
+                    .enumerate()
+                    .map(|(col, &h)| match char::from_digit(h, 10) {
+                        Some(c) if shown.contains((row, col)) => c,
+                        _ => '.',
+                    })
+                    .collect::<String>()
+            })
+            .join("\n")
+    }
 }
 
 fn parse_input() -> Map {
@@ -80,23 +167,48 @@ fn parse_input() -> Map {
 
 fn part1_solution() -> usize {
     let map = parse_input();
+    let analysis = TrailAnalysis::new(&map);
 
-    iter_map_positions(&map)
-        .filter(|pos| map[pos.row][pos.col] == 0)
-        .map(|pos| get_reachable_summits(&map, pos).len())
-        .sum()
+    analysis.trailheads(&map).iter().map(|t| t.score).sum()
 }
 
 fn part2_solution() -> usize {
     let map = parse_input();
+    let analysis = TrailAnalysis::new(&map);
 
-    iter_map_positions(&map)
-        .filter(|pos| map[pos.row][pos.col] == 0)
-        .map(|pos| get_rating(&map, pos))
-        .sum()
+    analysis.trailheads(&map).iter().map(|t| t.rating).sum()
+}
+
+fn print_trailheads() {
+    let map = parse_input();
+    let analysis = TrailAnalysis::new(&map);
+
+    println!("row,col,score,rating");
+    for trailhead in analysis.trailheads(&map) {
+        let (row, col) = trailhead.position;
+        println!("{},{},{},{}", row, col, trailhead.score, trailhead.rating);
+    }
+}
+
+// Usage: popular [TOP]
+fn print_popular(args: &[String]) {
+    let top = args.first().map_or(100, |arg| {
+        arg.parse().expect("Cannot parse number of cells!")
+    });
+
+    let map = parse_input();
+    let analysis = TrailAnalysis::new(&map);
+    println!("{}", analysis.render_popular(&map, top));
 }
 
 fn main() {
-    println!("{}", part1_solution());
-    println!("{}", part2_solution());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("trailheads") => print_trailheads(),
+        Some("popular") => print_popular(&args[1..]),
+        _ => {
+            println!("{}", part1_solution());
+            println!("{}", part2_solution());
+        }
+    }
 }
@@ -1,13 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
-use cached::proc_macro::cached;
-
-use aoc_rust_2024::{helpers::make_counter, io};
+use aoc_rust_2024::io;
+use num_bigint::BigUint;
 
 type Stone = u64;
-type Stones = HashMap<Stone, usize>;
 
-#[cached]
 fn blink_stone(stone: Stone) -> (Stone, Option<Stone>) {
     if stone == 0 {
         return (1, None);
@@ -25,16 +22,168 @@ fn blink_stone(stone: Stone) -> (Stone, Option<Stone>) {
     }
 }
 
-fn blink(stones: &mut Stones) {
-    let mut new_stones = HashMap::new();
-    for (&stone, &count) in stones.iter() {
-        let (s1, s2) = blink_stone(stone);
-        *new_stones.entry(s1).or_insert(0) += count;
-        if let Some(s2) = s2 {
-            *new_stones.entry(s2).or_insert(0) += count;
+// Every stone value reachable from the starting stones, and what each one
+// turns into when you blink. There are only a few thousand of them, so stones
+// are referred to by their index in `values` from here on.
+#[derive(Debug)]
+struct StoneGraph {
+    values: Vec<Stone>,
+    index: HashMap<Stone, usize>,
+    next: Vec<(usize, Option<usize>)>,
+}
+
+impl StoneGraph {
+    fn new(start: &[Stone]) -> Self {
+        let mut graph = Self {
+            values: vec![],
+            index: HashMap::new(),
+            next: vec![],
+        };
+
+        let mut q: VecDeque<Stone> = start.iter().copied().collect();
+        let mut blinks = HashMap::new();
+        while let Some(stone) = q.pop_front() {
+            if graph.index.contains_key(&stone) {
+                continue;
+            }
+            graph.index.insert(stone, graph.values.len());
+            graph.values.push(stone);
+
+            let (s1, s2) = blink_stone(stone);
+            blinks.insert(stone, (s1, s2));
+            q.push_back(s1);
+            q.extend(s2);
         }
+
+        graph.next = graph
+            .values
+            .iter()
+            .map(|stone| {
+                let (s1, s2) = blinks[stone];
+                (graph.index[&s1], s2.map(|s2| graph.index[&s2]))
+            })
+            .collect();
+        graph
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    // Each stone value and the values it splits into.
+    fn transitions(&self) -> impl Iterator<Item = (Stone, Vec<Stone>)> + '_ {
+        self.values
+            .iter()
+            .zip(&self.next)
+            .map(|(&stone, &(s1, s2))| {
+                let next = [Some(s1), s2].into_iter().flatten();
+                (stone, next.map(|i| self.values[i]).collect())
+            })
+    }
+}
+
+// Stone counts fit in a usize until a hundred or so blinks in, after which
+// they're switched over to arbitrary precision.
+#[derive(Clone, Debug)]
+enum Counts {
+    Small(Vec<usize>),
+    Big(Vec<BigUint>),
+}
+
+// How many stones there are of each value after some number of blinks.
+#[derive(Clone, Debug)]
+struct Population<'a> {
+    graph: &'a StoneGraph,
+    counts: Counts,
+    generation: usize,
+}
+
+impl<'a> Population<'a> {
+    fn new(graph: &'a StoneGraph, start: &[Stone]) -> Self {
+        let mut counts = vec![0; graph.len()];
+        for stone in start {
+            counts[graph.index[stone]] += 1;
+        }
+        Self {
+            graph,
+            counts: Counts::Small(counts),
+            generation: 0,
+        }
+    }
+
+    fn blink_small(&self, counts: &[usize]) -> Option<Vec<usize>> {
+        let mut new_counts = vec![0usize; counts.len()];
+        for (&(s1, s2), &count) in self.graph.next.iter().zip(counts) {
+            new_counts[s1] = new_counts[s1].checked_add(count)?;
+            if let Some(s2) = s2 {
+                new_counts[s2] = new_counts[s2].checked_add(count)?;
+            }
+        }
+        Some(new_counts)
+    }
+
+    fn blink_big(&self, counts: &[BigUint]) -> Vec<BigUint> {
+        let mut new_counts = vec![BigUint::ZERO; counts.len()];
+        for (&(s1, s2), count) in self.graph.next.iter().zip(counts) {
+            new_counts[s1] += count;
+            if let Some(s2) = s2 {
+                new_counts[s2] += count;
+            }
+        }
+        new_counts
+    }
+
+    fn blink(&mut self) {
+        self.counts = match &self.counts {
+            Counts::Small(counts) => match self.blink_small(counts) {
+                Some(new_counts) => Counts::Small(new_counts),
+                None => {
+                    let counts: Vec<BigUint> = counts.iter().map(|&c| c.into()).collect();
+                    Counts::Big(self.blink_big(&counts))
+                }
+            },
+            Counts::Big(counts) => Counts::Big(self.blink_big(counts)),
+        };
+        self.generation += 1;
+    }
+
+    fn total(&self) -> BigUint {
+        match &self.counts {
+            Counts::Small(counts) => counts.iter().map(|&c| c as u128).sum::<u128>().into(),
+            Counts::Big(counts) => counts.iter().sum(),
+        }
+    }
+
+    // The number of different values on the stones.
+    fn distinct(&self) -> usize {
+        match &self.counts {
+            Counts::Small(counts) => counts.iter().filter(|&&c| c > 0).count(),
+            Counts::Big(counts) => counts.iter().filter(|&c| c != &BigUint::ZERO).count(),
+        }
+    }
+}
+
+struct GenerationStats {
+    generation: usize,
+    total: BigUint,
+    distinct: usize,
+}
+
+// Stats for every generation up to and including `blinks`.
+fn simulate(graph: &StoneGraph, start: &[Stone], blinks: usize) -> Vec<GenerationStats> {
+    let mut population = Population::new(graph, start);
+    let mut stats = vec![];
+    loop {
+        stats.push(GenerationStats {
+            generation: population.generation,
+            total: population.total(),
+            distinct: population.distinct(),
+        });
+        if population.generation == blinks {
+            return stats;
+        }
+        population.blink();
     }
-    *stones = new_stones;
 }
 
 fn parse_input() -> Vec<Stone> {
@@ -44,27 +193,61 @@ fn parse_input() -> Vec<Stone> {
     input.split(' ').map(|n| n.parse().unwrap()).collect()
 }
 
-fn part1_solution() -> usize {
+fn count_stones(blinks: usize) -> BigUint {
     let stones = parse_input();
-    let mut stones = make_counter(stones.into_iter());
+    let graph = StoneGraph::new(&stones);
+    let mut population = Population::new(&graph, &stones);
 
-    for _ in 0..25 {
-        blink(&mut stones);
+    for _ in 0..blinks {
+        population.blink();
     }
-    stones.values().sum()
+    population.total()
 }
 
-fn part2_solution() -> usize {
+fn part1_solution() -> BigUint {
+    count_stones(25)
+}
+
+fn part2_solution() -> BigUint {
+    count_stones(75)
+}
+
+// Usage: stats BLINKS
+fn print_stats(args: &[String]) {
+    let blinks: usize = args
+        .first()
+        .expect("Missing number of blinks!")
+        .parse()
+        .expect("Cannot parse number of blinks!");
+
+    let stones = parse_input();
+    let graph = StoneGraph::new(&stones);
+
+    println!("generation,total,distinct");
+    for stats in simulate(&graph, &stones, blinks) {
+        println!("{},{},{}", stats.generation, stats.total, stats.distinct);
+    }
+}
+
+fn print_graph() {
     let stones = parse_input();
-    let mut stones = make_counter(stones.into_iter());
+    let graph = StoneGraph::new(&stones);
 
-    for _ in 0..75 {
-        blink(&mut stones);
+    for (stone, next) in graph.transitions() {
+        let next: Vec<String> = next.iter().map(Stone::to_string).collect();
+        println!("{} -> {}", stone, next.join(" "));
     }
-    stones.values().sum()
+    println!("{} reachable stone values", graph.len());
 }
 
 fn main() {
-    println!("{}", part1_solution());
-    println!("{}", part2_solution());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("stats") => print_stats(&args[1..]),
+        Some("graph") => print_graph(),
+        _ => {
+            println!("{}", part1_solution());
+            println!("{}", part2_solution());
+        }
+    }
 }
@@ -1,6 +1,11 @@
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 
-use aoc_rust_2024::io;
+use aoc_rust_2024::{
+    grid::{GridBitSet, GridMap},
+    io,
+    polygon::{Point, Polygon},
+};
+use itertools::Itertools;
 
 type Map = Vec<Vec<u8>>;
 
@@ -8,27 +13,9 @@ type Position = (usize, usize);
 
 #[derive(Debug)]
 struct Region {
-    plots: HashSet<Position>,
-    _plant: u8,
-}
-
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-enum FenceDirection {
-    Horizontal,
-    Veritcal,
-}
-
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-enum FenceOrientation {
-    NorthOrWestOfPlot,
-    SouthOrEastOfPlot,
-}
-
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-struct FencePiece {
-    position: Position,
-    dir: FenceDirection,
-    orientation: FenceOrientation,
+    id: usize,
+    plots: GridBitSet,
+    plant: u8,
 }
 
 impl Region {
@@ -36,143 +23,67 @@ impl Region {
         self.plots.len()
     }
 
-    fn get_perimeter(&self, map: &Map) -> Vec<FencePiece> {
-        let height = map.len();
-        let width = map[0].len();
-
-        let boundary_type_horiz = |(row, col): (usize, usize)| -> Option<FenceOrientation> {
-            if row == 0 {
-                if self.plots.contains(&(row, col)) {
-                    Some(FenceOrientation::NorthOrWestOfPlot)
-                } else {
-                    None
-                }
-            } else if row == height {
-                if self.plots.contains(&(row - 1, col)) {
-                    Some(FenceOrientation::SouthOrEastOfPlot)
-                } else {
-                    None
-                }
-            } else {
-                let has_above = self.plots.contains(&(row - 1, col));
-                let has_below = self.plots.contains(&(row, col));
-                if has_above && !has_below || has_below && !has_above {
-                    Some(if has_below {
-                        FenceOrientation::NorthOrWestOfPlot
-                    } else {
-                        FenceOrientation::SouthOrEastOfPlot
-                    })
-                } else {
-                    None
-                }
-            }
-        };
-
-        let boundary_type_vert = |(row, col): (usize, usize)| -> Option<FenceOrientation> {
-            if col == 0 {
-                if self.plots.contains(&(row, col)) {
-                    Some(FenceOrientation::NorthOrWestOfPlot)
-                } else {
-                    None
-                }
-            } else if col == width {
-                if self.plots.contains(&(row, col - 1)) {
-                    Some(FenceOrientation::SouthOrEastOfPlot)
-                } else {
-                    None
-                }
-            } else {
-                let has_left = self.plots.contains(&(row, col - 1));
-                let has_right = self.plots.contains(&(row, col));
-                if has_left && !has_right || has_right && !has_left {
-                    Some(if has_right {
-                        FenceOrientation::NorthOrWestOfPlot
-                    } else {
-                        FenceOrientation::SouthOrEastOfPlot
-                    })
-                } else {
-                    None
-                }
-            }
-        };
+    // The fence around the region and around any holes in it.
+    fn get_outlines(&self) -> Vec<Polygon> {
+        Polygon::from_cells(self.plots.iter().map(|(row, col)| (row as i64, col as i64)))
+    }
 
-        let mut perimeter = Vec::new();
-        for fence_row in 0..height + 1 {
-            for fence_col in 0..width {
-                if let Some(boundary_orientation) = boundary_type_horiz((fence_row, fence_col)) {
-                    // perimeter.push((fence_row, fence_col, true, boundary_orientation));
-                    perimeter.push(FencePiece {
-                        position: (fence_row, fence_col),
-                        dir: FenceDirection::Horizontal,
-                        orientation: boundary_orientation,
-                    });
-                }
-            }
-        }
+    fn get_perimeter(&self) -> usize {
+        self.get_outlines()
+            .iter()
+            .map(|outline| outline.boundary_length() as usize)
+            .sum()
+    }
 
-        for fence_col in 0..width + 1 {
-            for fence_row in 0..height {
-                if let Some(boundary_orientation) = boundary_type_vert((fence_row, fence_col)) {
-                    // println!("{}: {:?}", self.plant, (fence_row, fence_col));
-                    // perimeter.push((fence_row, fence_col, false, boundary_type));
-
-                    perimeter.push(FencePiece {
-                        position: (fence_row, fence_col),
-                        dir: FenceDirection::Veritcal,
-                        orientation: boundary_orientation,
-                    });
-                }
-            }
-        }
+    fn get_num_sides(&self) -> usize {
+        self.get_outlines().iter().map(Polygon::num_sides).sum()
+    }
 
-        perimeter
+    fn get_price(&self) -> usize {
+        self.get_area() * self.get_perimeter()
     }
-}
 
-fn perimeter_to_num_sides(perimeter: Vec<FencePiece>) -> usize {
-    let (mut horiz, mut vert): (Vec<FencePiece>, Vec<_>) = perimeter
-        .iter()
-        .partition(|fence_piece| matches!(fence_piece.dir, FenceDirection::Horizontal));
-
-    horiz.sort();
-    let mut horiz_sides_count = 1;
-    let (mut curr_row, mut curr_col) = horiz[0].position;
-    let mut curr_orientation = horiz[0].orientation;
-    for &fence_piece in horiz.iter().skip(1) {
-        let (row, col) = fence_piece.position;
-        let orientation = fence_piece.orientation;
-        if row != curr_row || col != curr_col + 1 || orientation != curr_orientation {
-            horiz_sides_count += 1;
-        }
-        curr_col = col;
-        curr_row = row;
-        curr_orientation = orientation;
+    fn get_bulk_price(&self) -> usize {
+        self.get_area() * self.get_num_sides()
     }
 
-    // bit of a hack to make sorting by column easier (just swap row/col positions in tuple)
-    for fence_piece in &mut vert {
-        *fence_piece = FencePiece {
-            position: (fence_piece.position.1, fence_piece.position.0),
-            dir: fence_piece.dir,
-            orientation: fence_piece.orientation,
-        };
+    // The top left and bottom right plots of the region.
+    fn get_bounding_box(&self) -> (Position, Position) {
+        let (min_row, max_row) = self
+            .plots
+            .iter()
+            .map(|p| p.0)
+            .minmax()
+            .into_option()
+            .unwrap();
+        let (min_col, max_col) = self
+            .plots
+            .iter()
+            .map(|p| p.1)
+            .minmax()
+            .into_option()
+            .unwrap();
+        ((min_row, min_col), (max_row, max_col))
     }
-    vert.sort();
-    let mut vert_sides_count = 1;
-    let (mut curr_col, mut curr_row) = horiz[0].position;
-    let mut curr_orientation = horiz[0].orientation;
-    for &fence_piece in horiz.iter().skip(1) {
-        let (col, row) = fence_piece.position;
-        let orientation = fence_piece.orientation;
-        if col != curr_col || row != curr_row + 1 || orientation != curr_orientation {
-            vert_sides_count += 1;
-        }
-        curr_row = row;
-        curr_col = col;
-        curr_orientation = orientation;
+
+    // Outlines of holes run anticlockwise.
+    fn has_holes(&self) -> bool {
+        self.get_outlines()
+            .iter()
+            .any(|outline| !outline.is_clockwise())
     }
 
-    horiz_sides_count + vert_sides_count
+    // Each fence as a closed polyline through its corner posts.
+    fn get_fence_polylines(&self) -> Vec<Vec<Point>> {
+        self.get_outlines()
+            .iter()
+            .map(|outline| {
+                let mut polyline = outline.vertices().to_vec();
+                polyline.push(polyline[0]);
+                polyline
+            })
+            .collect()
+    }
 }
 
 fn parse_input() -> Map {
@@ -203,39 +114,41 @@ fn get_alike_neighbours(pos: (usize, usize), map: &Map) -> Vec<(usize, usize)> {
 }
 
 fn get_regions(map: &Map) -> Vec<Region> {
-    let mut seen = HashSet::new();
+    let (height, width) = (map.len(), map[0].len());
+    let mut seen = GridBitSet::new(height, width);
 
-    let explore = |(row, col): (usize, usize), seen: &mut HashSet<(usize, usize)>| -> Region {
+    let explore = |(row, col): (usize, usize), seen: &mut GridBitSet| -> Region {
         let plant = map[row][col];
 
-        let mut region = HashSet::new();
+        let mut region = GridBitSet::new(height, width);
         let mut stack = vec![(row, col)];
         while let Some((row, col)) = stack.pop() {
-            if seen.contains(&(row, col)) {
+            if !seen.insert((row, col)) {
                 continue;
             }
             region.insert((row, col));
-            seen.insert((row, col));
 
             let nbrs = get_alike_neighbours((row, col), map);
             for n in nbrs {
-                if !seen.contains(&n) {
+                if !seen.contains(n) {
                     stack.push(n);
                 }
             }
         }
 
         Region {
+            id: 0,
             plots: region,
-            _plant: plant,
+            plant,
         }
     };
 
     let mut regions = Vec::new();
     for (row, line) in map.iter().enumerate() {
         for (col, _) in line.iter().enumerate() {
-            if !seen.contains(&(row, col)) {
-                let region = explore((row, col), &mut seen);
+            if !seen.contains((row, col)) {
+                let mut region = explore((row, col), &mut seen);
+                region.id = regions.len();
                 regions.push(region);
             }
         }
@@ -244,12 +157,82 @@ fn get_regions(map: &Map) -> Vec<Region> {
     regions
 }
 
+// The id of the region that each plot belongs to.
+fn get_region_lookup(map: &Map, regions: &[Region]) -> GridMap<usize> {
+    let mut lookup = GridMap::new(map.len(), map[0].len());
+    for region in regions {
+        for pos in region.plots.iter() {
+            lookup.insert(pos, region.id);
+        }
+    }
+    lookup
+}
+
+// For each region, the ids of the regions it shares a fence with.
+fn get_adjacent_regions(map: &Map, regions: &[Region]) -> Vec<HashSet<usize>> {
+    let lookup = get_region_lookup(map, regions);
+    let mut adjacent = vec![HashSet::new(); regions.len()];
+
+    for ((row, col), &id) in lookup.iter() {
+        let below = lookup.get((row + 1, col));
+        let right = lookup.get((row, col + 1));
+        for &other in [below, right].into_iter().flatten() {
+            if other != id {
+                adjacent[id].insert(other);
+                adjacent[other].insert(id);
+            }
+        }
+    }
+
+    adjacent
+}
+
+// For each region, the ids of the regions sitting inside its holes. These are
+// the regions that get cut off from the edge of the map when the enclosing
+// region is taken away.
+fn get_enclosed_regions(
+    map: &Map,
+    regions: &[Region],
+    adjacent: &[HashSet<usize>],
+) -> Vec<Vec<usize>> {
+    let (height, width) = (map.len(), map[0].len());
+    let border_regions: HashSet<usize> = get_region_lookup(map, regions)
+        .iter()
+        .filter(|&((row, col), _)| row == 0 || row == height - 1 || col == 0 || col == width - 1)
+        .map(|(_, &id)| id)
+        .collect();
+
+    regions
+        .iter()
+        .map(|region| {
+            if !region.has_holes() {
+                return vec![];
+            }
+
+            let mut reached: HashSet<usize> = border_regions.clone();
+            reached.remove(&region.id);
+            let mut q: VecDeque<usize> = reached.iter().copied().collect();
+            while let Some(id) = q.pop_front() {
+                for &n in &adjacent[id] {
+                    if n != region.id && reached.insert(n) {
+                        q.push_back(n);
+                    }
+                }
+            }
+
+            (0..regions.len())
+                .filter(|&id| id != region.id && !reached.contains(&id))
+                .collect()
+        })
+        .collect()
+}
+
 fn part1_solution() -> usize {
     let map = parse_input();
     let regions = get_regions(&map);
     regions
         .into_iter()
-        .map(|region| region.get_area() * region.get_perimeter(&map).len())
+        .map(|region| region.get_area() * region.get_perimeter())
         .sum()
 }
 
@@ -258,13 +241,190 @@ fn part2_solution() -> usize {
     let regions = get_regions(&map);
     regions
         .into_iter()
-        .map(|region| (region.get_area(), region.get_perimeter(&map)))
-        .map(|(area, perim)| (area, perimeter_to_num_sides(perim)))
-        .map(|(area, sides)| area * sides)
+        .map(|region| region.get_area() * region.get_num_sides())
         .sum()
 }
 
+struct RegionSummary {
+    id: usize,
+    plant: char,
+    area: usize,
+    perimeter: usize,
+    sides: usize,
+    price: usize,
+    bulk_price: usize,
+    bounding_box: (Position, Position),
+    neighbours: usize,
+    holes: Vec<usize>,
+}
+
+impl RegionSummary {
+    const COLUMNS: [&'static str; 10] = [
+        "id",
+        "plant",
+        "area",
+        "perimeter",
+        "sides",
+        "price",
+        "bulk_price",
+        "bounding_box",
+        "neighbours",
+        "holes",
+    ];
+
+    fn fields(&self) -> Vec<String> {
+        let ((top, left), (bottom, right)) = self.bounding_box;
+        vec![
+            self.id.to_string(),
+            self.plant.to_string(),
+            self.area.to_string(),
+            self.perimeter.to_string(),
+            self.sides.to_string(),
+            self.price.to_string(),
+            self.bulk_price.to_string(),
+            format!("{},{}-{},{}", top, left, bottom, right),
+            self.neighbours.to_string(),
+            self.holes.iter().join(" "),
+        ]
+    }
+
+    // Bounding boxes sort by their top left corner, then the bottom right.
+    fn sort_key(&self, column: &str) -> Vec<usize> {
+        let ((top, left), (bottom, right)) = self.bounding_box;
+        match column {
+            "id" => vec![self.id],
+            "plant" => vec![self.plant as usize],
+            "area" => vec![self.area],
+            "perimeter" => vec![self.perimeter],
+            "sides" => vec![self.sides],
+            "price" => vec![self.price],
+            "bulk_price" => vec![self.bulk_price],
+            "bounding_box" => vec![top, left, bottom, right],
+            "neighbours" => vec![self.neighbours],
+            "holes" => vec![self.holes.len()],
+            _ => panic!("Cannot sort by column '{}'", column),
+        }
+    }
+}
+
+fn get_region_summaries(map: &Map) -> Vec<RegionSummary> {
+    let regions = get_regions(map);
+    let adjacent = get_adjacent_regions(map, &regions);
+    let enclosed = get_enclosed_regions(map, &regions, &adjacent);
+
+    regions
+        .iter()
+        .map(|region| RegionSummary {
+            id: region.id,
+            plant: region.plant as char,
+            area: region.get_area(),
+            perimeter: region.get_perimeter(),
+            sides: region.get_num_sides(),
+            price: region.get_price(),
+            bulk_price: region.get_bulk_price(),
+            bounding_box: region.get_bounding_box(),
+            neighbours: adjacent[region.id].len(),
+            holes: enclosed[region.id].clone(),
+        })
+        .collect()
+}
+
+// Usage: report [--sort COLUMN] [--reverse] [--csv]
+fn print_report(args: &[String]) {
+    let map = parse_input();
+    let mut summaries = get_region_summaries(&map);
+
+    let mut args = args.iter();
+    let mut csv = false;
+    let mut reverse = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--sort" => {
+                let column = args.next().expect("Missing column to sort by!");
+                summaries.sort_by_key(|summary| summary.sort_key(column));
+            }
+            "--reverse" => reverse = true,
+            "--csv" => csv = true,
+            _ => panic!("Unknown argument '{}'", arg),
+        }
+    }
+    if reverse {
+        summaries.reverse();
+    }
+
+    if csv {
+        println!("{}", RegionSummary::COLUMNS.join(","));
+        for summary in &summaries {
+            let fields = summary.fields().into_iter().map(|field| {
+                if field.contains(',') {
+                    format!("\"{}\"", field)
+                } else {
+                    field
+                }
+            });
+            println!("{}", fields.format(","));
+        }
+    } else {
+        let rows = summaries.iter().map(RegionSummary::fields).collect_vec();
+        let widths = RegionSummary::COLUMNS
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                rows.iter()
+                    .map(|row| row[i].len())
+                    .chain([column.len()])
+                    .max()
+                    .unwrap()
+            })
+            .collect_vec();
+        let print_row = |row: Vec<&str>| {
+            let cells = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{:>width$}", cell));
+            println!("{}", cells.format("  "));
+        };
+
+        print_row(RegionSummary::COLUMNS.to_vec());
+        for row in &rows {
+            print_row(row.iter().map(String::as_str).collect());
+        }
+    }
+}
+
+// Usage: fences ID
+fn print_fences(args: &[String]) {
+    let map = parse_input();
+    let regions = get_regions(&map);
+    let id: usize = args
+        .first()
+        .expect("Missing region id!")
+        .parse()
+        .expect("Cannot parse region id!");
+
+    let region = regions
+        .get(id)
+        .unwrap_or_else(|| panic!("No such region {}, there are {}", id, regions.len()));
+
+    for polyline in region.get_fence_polylines() {
+        println!(
+            "{}",
+            polyline
+                .iter()
+                .map(|(row, col)| format!("({},{})", row, col))
+                .join(" -> ")
+        );
+    }
+}
+
 fn main() {
-    println!("{}", part1_solution());
-    println!("{}", part2_solution());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("report") => print_report(&args[1..]),
+        Some("fences") => print_fences(&args[1..]),
+        _ => {
+            println!("{}", part1_solution());
+            println!("{}", part2_solution());
+        }
+    }
 }
@@ -1,11 +1,229 @@
+use std::fmt;
+
 use aoc_rust_2024::io;
 use regex::Regex;
 
-#[derive(Debug)]
+const A_COST: i128 = 3;
+const B_COST: i128 = 1;
+const PART_2_OFFSET: i128 = 10000000000000;
+
+// Everything is stored as i128 so that prizes can be moved a long way off
+// without the arithmetic below overflowing.
+#[derive(Clone, Debug)]
 struct Game {
-    button_a: (u64, u64),
-    button_b: (u64, u64),
-    prize: (u64, u64),
+    button_a: (i128, i128),
+    button_b: (i128, i128),
+    prize: (i128, i128),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Unreachable {
+    // The buttons are independent, but the only way to reach the prize takes
+    // a fractional number of presses.
+    FractionalPresses,
+    // The only ways to reach the prize take a negative number of presses.
+    NegativePresses,
+    // The buttons both move the claw along the same line, and the prize is
+    // not on it.
+    OffLine,
+    // The prize is on the buttons' line, but no whole number of presses
+    // lands on it.
+    NoIntegerSolution,
+}
+
+impl fmt::Display for Unreachable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Unreachable::FractionalPresses => "would need a fractional number of presses",
+            Unreachable::NegativePresses => "would need a negative number of presses",
+            Unreachable::OffLine => "is not on the line the buttons move along",
+            Unreachable::NoIntegerSolution => "cannot be reached with whole presses",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Win {
+    a_presses: i128,
+    b_presses: i128,
+    tokens: i128,
+}
+
+impl Win {
+    fn new(a_presses: i128, b_presses: i128) -> Self {
+        Self {
+            a_presses,
+            b_presses,
+            tokens: a_presses * A_COST + b_presses * B_COST,
+        }
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// Returns (g, x, y) with a*x + b*y = g = gcd(a, b).
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
+    }
+}
+
+impl Game {
+    fn with_offset(&self, offset: i128) -> Self {
+        Self {
+            prize: (self.prize.0 + offset, self.prize.1 + offset),
+            ..self.clone()
+        }
+    }
+
+    fn solve(&self) -> Result<Win, Unreachable> {
+        // We want to solve the linear system:
+        //
+        //  t1*a + t2*c = y1
+        //  t1*b + t2*d = y2
+        //
+        // with non-negative integer solutions in t1,t2. This can be represented by the
+        // (matrix) equation:
+        //
+        //    [t1 t2] [a b] = [y1 y2]
+        //            [c d]
+        //
+        // If the buttons are linearly independent the determinant is non-zero, and the
+        // system can be solved explicitly with
+        //
+        //     [t1 t2] = [y1 y2][a b]^-1
+        //                      [c d]
+        //             = [y1 y2]([ d -b] / det)
+        //                      ([-c  a]      )
+        //
+        // giving a unique solution. Otherwise the buttons are collinear and there may be
+        // many solutions to choose between.
+        let (a, b) = self.button_a;
+        let (c, d) = self.button_b;
+        let (y1, y2) = self.prize;
+
+        let det = a * d - c * b;
+        if det == 0 {
+            return self.solve_collinear();
+        }
+
+        let (n1, n2) = (d * y1 - c * y2, -b * y1 + a * y2);
+        if n1 % det != 0 || n2 % det != 0 {
+            return Err(Unreachable::FractionalPresses);
+        }
+        let (t1, t2) = (n1 / det, n2 / det);
+        if t1 < 0 || t2 < 0 {
+            return Err(Unreachable::NegativePresses);
+        }
+
+        Ok(Win::new(t1, t2))
+    }
+
+    // Both buttons move the claw along the same line, so write everything as a
+    // multiple of the smallest step along it, u. With A = alpha*u, B = beta*u
+    // and the prize at pi*u, we want the cheapest non-negative t1,t2 with
+    //
+    //  alpha*t1 + beta*t2 = pi
+    fn solve_collinear(&self) -> Result<Win, Unreachable> {
+        let (a, b) = self.button_a;
+        let (c, d) = self.button_b;
+        let (y1, y2) = self.prize;
+
+        let Some(&(ux, uy)) = [(a, b), (c, d)].iter().find(|&&v| v != (0, 0)) else {
+            // Neither button does anything.
+            return if self.prize == (0, 0) {
+                Ok(Win::new(0, 0))
+            } else {
+                Err(Unreachable::OffLine)
+            };
+        };
+        let g = gcd(ux, uy);
+        let (ux, uy) = (ux / g, uy / g);
+
+        // How many steps of u it takes to get to (x, y), if it's on the line. Any
+        // point on the line is a whole number of steps away, as u is as short as
+        // it can be.
+        let steps = |(x, y): (i128, i128)| -> Option<i128> {
+            if x * uy != y * ux {
+                return None;
+            }
+            let (k, r) = if ux != 0 {
+                (x / ux, x % ux)
+            } else {
+                (y / uy, y % uy)
+            };
+            (r == 0).then_some(k)
+        };
+        let alpha = steps((a, b)).unwrap();
+        let beta = steps((c, d)).unwrap();
+        let pi = steps((y1, y2)).ok_or(Unreachable::OffLine)?;
+
+        if alpha == 0 || beta == 0 {
+            // Only one of the buttons moves the claw.
+            let (step, win): (i128, fn(i128) -> Win) = if alpha == 0 {
+                (beta, |t| Win::new(0, t))
+            } else {
+                (alpha, |t| Win::new(t, 0))
+            };
+            return if pi % step != 0 {
+                Err(Unreachable::NoIntegerSolution)
+            } else if pi / step < 0 {
+                Err(Unreachable::NegativePresses)
+            } else {
+                Ok(win(pi / step))
+            };
+        }
+
+        // Turn u round if need be so that A moves the claw forwards along it.
+        let (alpha, beta, pi) = if alpha < 0 {
+            (-alpha, -beta, -pi)
+        } else {
+            (alpha, beta, pi)
+        };
+        let (g, x, y) = extended_gcd(alpha, beta);
+        let (g, x, y) = if g < 0 { (-g, -x, -y) } else { (g, x, y) };
+        if pi % g != 0 {
+            return Err(Unreachable::NoIntegerSolution);
+        }
+
+        // Every solution is t1 = t1_0 + k*beta/g, t2 = t2_0 - k*alpha/g for some
+        // integer k, and we need both to be non-negative. Keeping t2 from going
+        // negative caps k. If B moves forwards too, keeping t1 from going
+        // negative puts a floor under k as well, but if it moves backwards both
+        // presses can go up together forever and t1 only caps k again.
+        let (t1_0, t2_0) = (x * (pi / g), y * (pi / g));
+        let (step1, step2) = (beta / g, alpha / g);
+        let mut k_max = t2_0.div_euclid(step2);
+        let k_min = if step1 > 0 {
+            Some((-t1_0).div_euclid(step1) + i128::from((-t1_0).rem_euclid(step1) != 0))
+        } else {
+            k_max = k_max.min(t1_0.div_euclid(-step1));
+            None
+        };
+        if k_min.is_some_and(|k_min| k_min > k_max) {
+            return Err(Unreachable::NegativePresses);
+        }
+
+        // The cost changes by a fixed amount with each step in k, so the
+        // cheapest solution is at one end of the range. With no floor on k,
+        // lowering it presses both buttons more, so that's never cheaper.
+        let cost_per_step = A_COST * step1 - B_COST * step2;
+        let k = match k_min {
+            Some(k_min) if cost_per_step >= 0 => k_min,
+            _ => k_max,
+        };
+        Ok(Win::new(t1_0 + k * step1, t2_0 - k * step2))
+    }
 }
 
 fn parse_input() -> Vec<Game> {
@@ -16,7 +234,7 @@ fn parse_input() -> Vec<Game> {
 
     let mut games = Vec::new();
     for game in input.split("\n\n") {
-        let number_pairs: Vec<(u64, u64)> = number_pair_regex
+        let number_pairs: Vec<(i128, i128)> = number_pair_regex
             .captures_iter(game)
             .map(|v| v.extract())
             .map(|(_, [v1, v2])| (v1.parse().unwrap(), v2.parse().unwrap()))
@@ -31,68 +249,52 @@ fn parse_input() -> Vec<Game> {
     games
 }
 
-fn get_min_tokens(game: &Game) -> Option<u64> {
-    // We want to solve the linear system:
-    //
-    //  t1*a + t2*c = y1
-    //  t1*b + t2*d = y2
-    //
-    // with positive integer solutions in y1,y2. This can be represented by the (matrix)
-    // equation:
-    //
-    //    [t1 t2] [a b] = [y1 y2]
-    //            [c d]
-    //
-    // which can be solved explicitly with
-    //
-    //     [t1 t2] = [y1 y2][a b]^-1
-    //                      [c d]
-    //             = [y1 y2]([ d -b] / det)
-    //                      ([-c  a]      )
-    //
-    // We are exploiting the fact both row vectors (buttons) in each "game" are linearly
-    // independent, resulting in a non-zero determinant and a uniqe solution.
-    let (a, b, c, d) = (
-        game.button_a.0 as i64,
-        game.button_a.1 as i64,
-        game.button_b.0 as i64,
-        game.button_b.1 as i64,
-    );
-    let det = a * d - c * b;
-    assert_ne!(det, 0, "zero determinant!");
-
-    let (y1, y2) = (game.prize.0 as i64, game.prize.1 as i64);
-
-    if (d * y1 - c * y2) % det != 0 || (-b * y1 + a * y2) % det != 0 {
-        // can't use this determinant, would result in non-integer solution for t1 or t2
-        return None;
-    }
-
-    let t1 = (d * y1 - c * y2) / det;
-    let t2 = (-b * y1 + a * y2) / det;
-
-    assert!(t1 > 0);
-    assert!(t2 > 0);
+fn get_min_tokens(games: &[Game], offset: i128) -> i128 {
+    games
+        .iter()
+        .filter_map(|game| game.with_offset(offset).solve().ok())
+        .map(|win| win.tokens)
+        .sum()
+}
 
-    Some((t1 * 3 + t2) as u64)
+fn part1_solution() -> i128 {
+    get_min_tokens(&parse_input(), 0)
 }
 
-fn part1_solution() -> u64 {
-    let games = parse_input();
-    games.into_iter().flat_map(|g| get_min_tokens(&g)).sum()
+fn part2_solution() -> i128 {
+    get_min_tokens(&parse_input(), PART_2_OFFSET)
 }
 
-fn part2_solution() -> u64 {
-    const PART_2_OFFSET: u64 = 10000000000000;
-    let mut games = parse_input();
-    for game in &mut games {
-        game.prize.0 += PART_2_OFFSET;
-        game.prize.1 += PART_2_OFFSET;
+// Usage: solve [OFFSET]
+fn print_solutions(args: &[String]) {
+    let offset: i128 = args
+        .first()
+        .map_or(0, |arg| arg.parse().expect("Cannot parse prize offset!"));
+
+    let games = parse_input();
+    for (i, game) in games.iter().enumerate() {
+        let game = game.with_offset(offset);
+        match game.solve() {
+            Ok(win) => println!(
+                "Machine {}: {} A + {} B presses for {} tokens",
+                i + 1,
+                win.a_presses,
+                win.b_presses,
+                win.tokens
+            ),
+            Err(reason) => println!("Machine {}: prize at {:?} {}", i + 1, game.prize, reason),
+        }
     }
-    games.into_iter().flat_map(|g| get_min_tokens(&g)).sum()
+    println!("{} tokens in total", get_min_tokens(&games, offset));
 }
 
 fn main() {
-    println!("{}", part1_solution());
-    println!("{}", part2_solution());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("solve") => print_solutions(&args[1..]),
+        _ => {
+            println!("{}", part1_solution());
+            println!("{}", part2_solution());
+        }
+    }
 }
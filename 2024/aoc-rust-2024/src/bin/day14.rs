@@ -1,7 +1,10 @@
-use aoc_rust_2024::{helpers::make_counter, io};
+use std::collections::VecDeque;
+
+use aoc_rust_2024::{grid::GridBitSet, helpers::make_counter, io};
+use itertools::Itertools;
 use regex::Regex;
 
-const TREE_MARKER: &str = "XXXXXXXXXX";
+const PUZZLE_SIZE: Vec2 = Vec2 { x: 101, y: 103 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Vec2 {
@@ -9,6 +12,19 @@ struct Vec2 {
     y: i32,
 }
 
+impl From<&str> for Vec2 {
+    // Parses "WIDTHxHEIGHT".
+    fn from(value: &str) -> Self {
+        let (x, y) = value
+            .split_once('x')
+            .unwrap_or_else(|| panic!("Expected a size like 101x103, got '{}'", value));
+        Vec2 {
+            x: x.parse().unwrap(),
+            y: y.parse().unwrap(),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct Robot {
     start_pos: Vec2,
@@ -17,12 +33,14 @@ struct Robot {
 
 impl Robot {
     fn get_new_pos(&self, time_secs: u32, corner: Vec2) -> Vec2 {
-        let time_secs = time_secs as i32;
+        let time_secs = time_secs as i64;
         let Vec2 { x: max_x, y: max_y } = corner;
 
         Vec2 {
-            x: (self.start_pos.x + time_secs * self.velocity.x).rem_euclid(max_x),
-            y: (self.start_pos.y + time_secs * self.velocity.y).rem_euclid(max_y),
+            x: (self.start_pos.x as i64 + time_secs * self.velocity.x as i64)
+                .rem_euclid(max_x as i64) as i32,
+            y: (self.start_pos.y as i64 + time_secs * self.velocity.y as i64)
+                .rem_euclid(max_y as i64) as i32,
         }
     }
 }
@@ -63,80 +81,307 @@ fn position_to_quadrant(pos: Vec2, corner: Vec2) -> Option<u8> {
     }
 }
 
-// fn get_noise_score(lines: &Vec<String>) -> usize {
-//     // a higher score means less noise
-//     let mut score = 0;
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
 
-//     for line in lines {
-//         score += line.chars().filter(|&c| c == '.').count();
-//     }
-//     score
-// }
+// The robots' positions at a single moment.
+struct Frame {
+    positions: Vec<Vec2>,
+    size: Vec2,
+}
 
-fn print_robots(robots: &[Robot], time_secs: u32, corner: Vec2) -> bool {
-    let Vec2 { x: max_x, y: max_y } = corner;
+impl Frame {
+    // An empty quadrant makes this 0.
+    fn safety_factor(&self) -> usize {
+        let mut counts = [0; 4];
+        for &pos in &self.positions {
+            if let Some(quadrant) = position_to_quadrant(pos, self.size) {
+                counts[quadrant as usize - 1] += 1;
+            }
+        }
+        counts.iter().product()
+    }
 
-    let new_positions: Vec<Vec2> = robots
-        .iter()
-        .map(|r| r.get_new_pos(time_secs, corner))
-        .collect();
-
-    let counts = make_counter(new_positions.into_iter());
-    let mut lines = Vec::new();
-    let mut found = false;
-    for y in 0..max_y {
-        let line: String = (0..max_x)
-            .map(|x| {
-                if counts.contains_key(&Vec2 { x, y }) {
-                    'X'
-                } else {
-                    '.'
+    fn occupied(&self) -> GridBitSet {
+        let mut occupied = GridBitSet::new(self.size.y as usize, self.size.x as usize);
+        occupied.extend(
+            self.positions
+                .iter()
+                .map(|pos| (pos.y as usize, pos.x as usize)),
+        );
+        occupied
+    }
+
+    // The most robots that are all joined up, horizontally or vertically.
+    fn largest_component(&self) -> usize {
+        let occupied = self.occupied();
+        let mut seen = GridBitSet::new(occupied.height(), occupied.width());
+        let mut largest = 0;
+
+        for start in occupied.iter() {
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut size = 0;
+            let mut q = VecDeque::from([start]);
+            while let Some((row, col)) = q.pop_front() {
+                size += 1;
+                let nbrs = [
+                    (row.wrapping_sub(1), col),
+                    (row + 1, col),
+                    (row, col.wrapping_sub(1)),
+                    (row, col + 1),
+                ];
+                for n in nbrs {
+                    if occupied.contains(n) && seen.insert(n) {
+                        q.push_back(n);
+                    }
                 }
+            }
+            largest = largest.max(size);
+        }
+        largest
+    }
+
+    fn render(&self) -> String {
+        let occupied = self.occupied();
+        (0..self.size.y as usize)
+            .map(|row| {
+                (0..self.size.x as usize)
+                    .map(|col| {
+                        if occupied.contains((row, col)) {
+                            'X'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
             })
-            .collect();
-        if line.contains(TREE_MARKER) {
-            found = true;
+            .join("\n")
+    }
+
+    // A plain PBM image, one pixel per tile.
+    fn to_pbm(&self) -> String {
+        let occupied = self.occupied();
+        let mut rows = (0..self.size.y as usize).map(|row| {
+            (0..self.size.x as usize)
+                .map(|col| {
+                    if occupied.contains((row, col)) {
+                        '1'
+                    } else {
+                        '0'
+                    }
+                })
+                .join(" ")
+        });
+        format!("P1\n{} {}\n{}\n", self.size.x, self.size.y, rows.join("\n"))
+    }
+}
+
+// Scores for how ordered a frame looks. Lower scores are more ordered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Metric {
+    // How spread out the robots are, summed over both axes.
+    Variance,
+    // The entropy of the robots' columns plus the entropy of their rows.
+    Entropy,
+    // The size of the biggest clump of robots, negated.
+    LargestComponent,
+    // The puzzle's safety factor, which drops when robots bunch up in one
+    // quadrant.
+    SafetyFactor,
+}
+
+impl From<&str> for Metric {
+    fn from(value: &str) -> Self {
+        match value {
+            "variance" => Metric::Variance,
+            "entropy" => Metric::Entropy,
+            "component" => Metric::LargestComponent,
+            "safety" => Metric::SafetyFactor,
+            _ => panic!("Unknown metric '{}'", value),
+        }
+    }
+}
+
+fn variance(values: &[i32]) -> f64 {
+    let n = values.len() as f64;
+    let mean = values.iter().map(|&v| v as f64).sum::<f64>() / n;
+    values
+        .iter()
+        .map(|&v| (v as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n
+}
+
+fn entropy(values: &[i32]) -> f64 {
+    let n = values.len() as f64;
+    make_counter(values.iter())
+        .values()
+        .map(|&count| {
+            let p = count as f64 / n;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+impl Metric {
+    // Whether the score is a sum of a part that only depends on the robots'
+    // x coordinates and a part that only depends on their y coordinates.
+    fn is_separable(&self) -> bool {
+        matches!(self, Metric::Variance | Metric::Entropy)
+    }
+
+    fn axis_score(&self, values: &[i32]) -> f64 {
+        match self {
+            Metric::Variance => variance(values),
+            Metric::Entropy => entropy(values),
+            _ => unreachable!("{:?} is not separable", self),
         }
-        lines.push(line);
     }
 
-    if found {
-        for line in lines {
-            println!("{}", line);
+    fn score(&self, frame: &Frame) -> f64 {
+        match self {
+            Metric::Variance | Metric::Entropy => {
+                let xs = frame.positions.iter().map(|pos| pos.x).collect_vec();
+                let ys = frame.positions.iter().map(|pos| pos.y).collect_vec();
+                self.axis_score(&xs) + self.axis_score(&ys)
+            }
+            Metric::LargestComponent => -(frame.largest_component() as f64),
+            Metric::SafetyFactor => frame.safety_factor() as f64,
         }
     }
-    found
 }
 
-fn part1_solution() -> u32 {
-    let robots = parse_input();
-    let corner = Vec2 { x: 101, y: 103 };
+struct Swarm {
+    robots: Vec<Robot>,
+    size: Vec2,
+}
+
+impl Swarm {
+    fn frame(&self, time_secs: u32) -> Frame {
+        Frame {
+            positions: self
+                .robots
+                .iter()
+                .map(|r| r.get_new_pos(time_secs, self.size))
+                .collect(),
+            size: self.size,
+        }
+    }
+
+    // Every robot is back where it started after this many seconds.
+    fn period(&self) -> u32 {
+        let (w, h) = (self.size.x as u32, self.size.y as u32);
+        w / gcd(w, h) * h
+    }
 
-    let new_positions: Vec<u8> = robots
-        .into_iter()
-        .map(|r| r.get_new_pos(100, corner))
-        .filter_map(|pos| position_to_quadrant(pos, corner))
-        .collect();
+    // The time in 0..period that the axis score is lowest, where x coordinates
+    // repeat every `period` seconds.
+    fn best_axis_time(&self, metric: Metric, period: u32, axis: impl Fn(Vec2) -> i32) -> u32 {
+        (0..period)
+            .map(|t| {
+                let values = self
+                    .robots
+                    .iter()
+                    .map(|r| axis(r.get_new_pos(t, self.size)))
+                    .collect_vec();
+                (t, metric.axis_score(&values))
+            })
+            .min_by(|(_, s1), (_, s2)| s1.total_cmp(s2))
+            .map(|(t, _)| t)
+            .unwrap()
+    }
+
+    // The time that the frame scores lowest on `metric`. Separable metrics
+    // only need to look at one period of each axis, with the two times
+    // stitched back together using the Chinese remainder theorem.
+    fn most_ordered_time(&self, metric: Metric) -> u32 {
+        let (w, h) = (self.size.x as u32, self.size.y as u32);
+        if metric.is_separable() && gcd(w, h) == 1 {
+            let tx = self.best_axis_time(metric, w, |pos| pos.x);
+            let ty = self.best_axis_time(metric, h, |pos| pos.y);
+            // Solve t = tx (mod w), t = ty (mod h) with t = tx + k*w, which
+            // needs k = (ty - tx) * w^-1 (mod h).
+            let w_inv = (0..h).find(|&k| (k * w) % h == 1 % h).unwrap();
+            let k = ((ty + h - tx % h) % h) * w_inv % h;
+            return tx + k * w;
+        }
 
-    let counts = make_counter(new_positions.into_iter());
-    println!("{:?}", counts);
-    let result: usize = counts.values().product();
-    result as u32
+        (0..self.period())
+            .map(|t| (t, metric.score(&self.frame(t))))
+            .min_by(|(_, s1), (_, s2)| s1.total_cmp(s2))
+            .map(|(t, _)| t)
+            .unwrap()
+    }
+}
+
+fn part1_solution() -> u32 {
+    let swarm = Swarm {
+        robots: parse_input(),
+        size: PUZZLE_SIZE,
+    };
+    swarm.frame(100).safety_factor() as u32
 }
 
 fn part2_solution() -> u32 {
-    let robots = parse_input();
-    let corner = Vec2 { x: 101, y: 103 };
+    let swarm = Swarm {
+        robots: parse_input(),
+        size: PUZZLE_SIZE,
+    };
+    swarm.most_ordered_time(Metric::Variance)
+}
 
-    for t in 1..101 * 103 {
-        if print_robots(&robots, t, corner) {
-            return t;
+// Usage: find [--metric variance|entropy|component|safety] [--size WxH]
+//        render SECONDS [--pbm] [--size WxH]
+fn print_frame(mode: &str, args: &[String]) {
+    let mut args = args.iter();
+    let mut time_secs = None;
+    let mut metric = Metric::Variance;
+    let mut size = PUZZLE_SIZE;
+    let mut pbm = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--metric" => metric = args.next().expect("Missing metric!").as_str().into(),
+            "--size" => size = args.next().expect("Missing size!").as_str().into(),
+            "--pbm" => pbm = true,
+            _ if mode == "render" && time_secs.is_none() => {
+                time_secs = Some(arg.parse().expect("Cannot parse time!"))
+            }
+            _ => panic!("Unknown argument '{}'", arg),
         }
     }
-    panic!("NO SOLUTION!");
+
+    let swarm = Swarm {
+        robots: parse_input(),
+        size,
+    };
+    let time_secs = match mode {
+        "find" => swarm.most_ordered_time(metric),
+        _ => time_secs.expect("Missing time to render!"),
+    };
+
+    let frame = swarm.frame(time_secs);
+    if pbm {
+        print!("{}", frame.to_pbm());
+    } else {
+        println!("{}", frame.render());
+        println!("After {} seconds", time_secs);
+    }
 }
 
 fn main() {
-    println!("{}", part1_solution());
-    println!("{}", part2_solution());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some(mode @ ("find" | "render")) => print_frame(mode, &args[1..]),
+        _ => {
+            println!("{}", part1_solution());
+            println!("{}", part2_solution());
+        }
+    }
 }
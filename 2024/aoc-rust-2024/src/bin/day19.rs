@@ -1,6 +1,6 @@
+use std::{collections::HashMap, iter};
+
 use aoc_rust_2024::io;
-use cached::{proc_macro::cached, SizedCache};
-use trie_rs::{inc_search::Answer, Trie, TrieBuilder};
 
 fn parse_input() -> (Vec<String>, Vec<String>) {
     let input = io::get_puzzle_input(19);
@@ -15,65 +15,193 @@ fn parse_input() -> (Vec<String>, Vec<String>) {
     (available_towels, designs)
 }
 
-#[cached(
-    ty = "SizedCache<String, u64>",
-    create = "{ SizedCache::with_size(10000) }",
-    convert = r#"{ format!("{}", design) }"#
-)]
-fn num_possible_designs(design: &str, trie: &Trie<u8>) -> u64 {
-    if design.is_empty() {
-        return 1;
+#[derive(Debug, Default)]
+struct TrieNode {
+    children: HashMap<u8, usize>,
+    is_towel: bool,
+}
+
+// A prefix tree of towel patterns. Node 0 is the root.
+#[derive(Debug)]
+struct TowelTrie {
+    nodes: Vec<TrieNode>,
+}
+
+impl TowelTrie {
+    fn new(towels: &[String]) -> Self {
+        let mut nodes = vec![TrieNode::default()];
+        for towel in towels {
+            let mut node = 0;
+            for &c in towel.as_bytes() {
+                node = match nodes[node].children.get(&c) {
+                    Some(&child) => child,
+                    None => {
+                        nodes.push(TrieNode::default());
+                        let child = nodes.len() - 1;
+                        nodes[node].children.insert(c, child);
+                        child
+                    }
+                };
+            }
+            nodes[node].is_towel = true;
+        }
+        Self { nodes }
+    }
+
+    // The end of every towel that matches `design` from `start` onwards, in
+    // increasing order.
+    fn matches<'a>(&'a self, design: &'a [u8], start: usize) -> impl Iterator<Item = usize> + 'a {
+        let mut node = 0;
+        design[start..]
+            .iter()
+            .map_while(move |c| {
+                node = *self.nodes[node].children.get(c)?;
+                Some(node)
+            })
+            .enumerate()
+            .filter(|&(_, node)| self.nodes[node].is_towel)
+            .map(move |(i, _)| start + i + 1)
     }
+}
 
-    let mut inc_search = trie.inc_search();
-    let mut good_prefix_lengths = vec![];
-    for i in 0..design.len() {
-        match inc_search.query(&design.as_bytes()[i]) {
-            None => break,
-            Some(Answer::Match | Answer::PrefixAndMatch) => good_prefix_lengths.push(i + 1),
-            Some(_) => continue,
+// Every way of making a single design out of towels, worked out from the end
+// of the design backwards.
+struct Decompositions<'a> {
+    design: &'a str,
+    // ends[i] are the ends of the towels that can go at position i.
+    ends: Vec<Vec<usize>>,
+    // ways[i] is the number of ways to make design[i..].
+    ways: Vec<u64>,
+    // fewest[i] is the fewest towels that make design[i..], if any do.
+    fewest: Vec<Option<usize>>,
+}
+
+impl<'a> Decompositions<'a> {
+    fn new(design: &'a str, trie: &TowelTrie) -> Self {
+        let n = design.len();
+        let ends: Vec<Vec<usize>> = (0..n)
+            .map(|start| trie.matches(design.as_bytes(), start).collect())
+            .collect();
+
+        let mut ways = vec![0; n + 1];
+        let mut fewest = vec![None; n + 1];
+        ways[n] = 1;
+        fewest[n] = Some(0);
+        for i in (0..n).rev() {
+            ways[i] = ends[i].iter().map(|&end| ways[end]).sum();
+            fewest[i] = ends[i]
+                .iter()
+                .filter_map(|&end| fewest[end])
+                .min()
+                .map(|towels| towels + 1);
+        }
+
+        Self {
+            design,
+            ends,
+            ways,
+            fewest,
         }
     }
 
-    let mut total_possible = 0;
-    for prefix_length in good_prefix_lengths {
-        total_possible += num_possible_designs(&design[prefix_length..], trie);
+    fn count(&self) -> u64 {
+        self.ways[0]
+    }
+
+    fn is_possible(&self) -> bool {
+        self.count() > 0
     }
 
-    return total_possible;
+    fn min_towels(&self) -> Option<usize> {
+        self.fewest[0]
+    }
+
+    // Each decomposition as a list of towels, skipping any dead ends.
+    fn iter(&self) -> impl Iterator<Item = Vec<&'a str>> + '_ {
+        let n = self.design.len();
+        let mut stack = if self.is_possible() {
+            vec![(0, vec![])]
+        } else {
+            vec![]
+        };
+
+        iter::from_fn(move || {
+            while let Some((start, towels)) = stack.pop() {
+                if start == n {
+                    return Some(towels);
+                }
+                for &end in self.ends[start].iter().rev() {
+                    if self.ways[end] > 0 {
+                        let mut towels = towels.clone();
+                        towels.push(&self.design[start..end]);
+                        stack.push((end, towels));
+                    }
+                }
+            }
+            None
+        })
+    }
 }
 
 fn part1_solution() -> usize {
     let (available_towels, designs) = parse_input();
-
-    let mut trie_builder = TrieBuilder::new();
-    for towel in available_towels {
-        trie_builder.push(&towel);
-    }
-    let trie = trie_builder.build();
+    let trie = TowelTrie::new(&available_towels);
 
     designs
-        .into_iter()
-        .filter(|design| num_possible_designs(design, &trie) > 0)
+        .iter()
+        .filter(|design| Decompositions::new(design, &trie).is_possible())
         .count()
 }
 
 fn part2_solution() -> u64 {
     let (available_towels, designs) = parse_input();
-
-    let mut trie_builder = TrieBuilder::new();
-    for towel in available_towels {
-        trie_builder.push(&towel);
-    }
-    let trie = trie_builder.build();
+    let trie = TowelTrie::new(&available_towels);
 
     designs
-        .into_iter()
-        .map(|design| num_possible_designs(&design, &trie))
+        .iter()
+        .map(|design| Decompositions::new(design, &trie).count())
         .sum()
 }
 
+// Usage: decompose DESIGN_NUMBER [LIMIT]
+fn print_decompositions(args: &[String]) {
+    let number: usize = args
+        .first()
+        .expect("Missing design number!")
+        .parse()
+        .expect("Cannot parse design number!");
+    let limit = args.get(1).map_or(10, |arg| {
+        arg.parse().expect("Cannot parse decomposition limit!")
+    });
+
+    let (available_towels, designs) = parse_input();
+    let trie = TowelTrie::new(&available_towels);
+    let design = &designs[number - 1];
+    let decompositions = Decompositions::new(design, &trie);
+
+    println!("{}", design);
+    match decompositions.min_towels() {
+        None => println!("Cannot be made from the available towels"),
+        Some(towels) => {
+            println!(
+                "{} ways to make it, using at least {} towels",
+                decompositions.count(),
+                towels
+            );
+            for towels in decompositions.iter().take(limit) {
+                println!("{}", towels.join(" "));
+            }
+        }
+    }
+}
+
 fn main() {
-    println!("{}", part1_solution());
-    println!("{}", part2_solution());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("decompose") => print_decompositions(&args[1..]),
+        _ => {
+            println!("{}", part1_solution());
+            println!("{}", part2_solution());
+        }
+    }
 }
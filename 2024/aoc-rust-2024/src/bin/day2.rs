@@ -1,53 +1,217 @@
+use std::{collections::HashMap, fmt, ops::RangeInclusive};
+
 use aoc_rust_2024::io;
+use itertools::Itertools;
 
 type Report = Vec<u32>;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Reason {
+    // Levels going up then down, or the other way round.
+    DirectionChange,
+    StepTooLarge,
+    StepTooSmall,
+    // Two levels in a row the same.
+    Plateau,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Reason::DirectionChange => "direction change",
+            Reason::StepTooLarge => "step too large",
+            Reason::StepTooSmall => "step too small",
+            Reason::Plateau => "plateau",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+// The first step in a report that isn't safe, from `index` to `index + 1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Problem {
+    index: usize,
+    reason: Reason,
+}
+
+struct SafetyChecker {
+    // How much the levels can change by in one step.
+    steps: RangeInclusive<u32>,
+}
+
+impl SafetyChecker {
+    fn check(&self, report: &Report) -> Result<(), Problem> {
+        let mut direction = None;
+        for (index, (&curr, &next)) in report.iter().tuple_windows().enumerate() {
+            let step = curr.abs_diff(next);
+            // A step of 0 doesn't go either way, so it only matters if
+            // it's too small.
+            let reason = if step == 0 {
+                (!self.steps.contains(&0)).then_some(Reason::Plateau)
+            } else if *direction.get_or_insert(next > curr) != (next > curr) {
+                Some(Reason::DirectionChange)
+            } else if step > *self.steps.end() {
+                Some(Reason::StepTooLarge)
+            } else if step < *self.steps.start() {
+                Some(Reason::StepTooSmall)
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(Problem { index, reason });
+            }
+        }
+        Ok(())
+    }
+
+    fn is_safe(&self, report: &Report) -> bool {
+        self.check(report).is_ok()
+    }
+
+    // The fewest levels to drop to make the report safe, by finding the
+    // longest run of levels that could be kept. A level can follow any
+    // earlier one that's an allowed step below it, so keeping the best run
+    // ending at each value makes this linear in the length of the report for
+    // fixed step bounds.
+    fn levels_to_drop(&self, report: &Report) -> Vec<usize> {
+        let longest_kept = |increasing: bool| -> Vec<bool> {
+            // For each value, the length of the longest run ending there and
+            // the index it ends at.
+            let mut best_ending_at: HashMap<i64, (usize, usize)> = HashMap::new();
+            let mut previous = vec![None; report.len()];
+            let mut best: Option<(usize, usize)> = None;
+
+            for (i, &level) in report.iter().enumerate() {
+                let level = if increasing {
+                    level as i64
+                } else {
+                    -(level as i64)
+                };
+                let before = self
+                    .steps
+                    .clone()
+                    .filter_map(|step| best_ending_at.get(&(level - step as i64)))
+                    .max()
+                    .copied();
+
+                previous[i] = before.map(|(_, j)| j);
+                let run = (before.map_or(1, |(len, _)| len + 1), i);
+                let entry = best_ending_at.entry(level).or_insert(run);
+                *entry = (*entry).max(run);
+                best = best.max(Some(run));
+            }
+
+            let mut kept = vec![false; report.len()];
+            let mut i = best.map(|(_, i)| i);
+            while let Some(j) = i {
+                kept[j] = true;
+                i = previous[j];
+            }
+            kept
+        };
+
+        let kept = [longest_kept(true), longest_kept(false)]
+            .into_iter()
+            .max_by_key(|kept| kept.iter().filter(|&&k| k).count())
+            .unwrap();
+        (0..report.len()).filter(|&i| !kept[i]).collect()
+    }
+
+    fn is_safe_with_dampener(&self, report: &Report, tolerance: usize) -> bool {
+        self.levels_to_drop(report).len() <= tolerance
+    }
+}
+
 fn parse_input() -> Vec<Vec<u32>> {
     let input = io::get_puzzle_input(2);
     let input = input.trim();
 
     input
         .lines()
-        .map(|line| line.split(' ').map(|val| val.parse().unwrap()).collect())
+        .map(|line| {
+            line.split_whitespace()
+                .map(|val| val.parse().unwrap())
+                .collect()
+        })
         .collect()
 }
 
-fn is_safe(report: &Report) -> bool {
-    let sign = (report[1] as i32 - report[0] as i32).signum();
+const PUZZLE_CHECKER: SafetyChecker = SafetyChecker { steps: 1..=3 };
 
-    report
-        .iter()
-        .zip(report.iter().skip(1))
-        .all(|(&curr, &next)| {
-            let difference = next as i32 - curr as i32;
-            difference.signum() == sign && (1..=3).contains(&difference.abs())
-        })
-}
+fn part1_solution() -> u32 {
+    let reports = parse_input();
 
-fn is_safe_with_dampner(report: &Report) -> bool {
-    (0..report.len()).any(|index_to_skip| {
-        let skipped: Vec<_> = report
-            .iter()
-            .enumerate()
-            .filter_map(|(i, &val)| if i != index_to_skip { Some(val) } else { None })
-            .collect();
-        is_safe(&skipped)
-    })
+    reports
+        .into_iter()
+        .filter(|report| PUZZLE_CHECKER.is_safe(report))
+        .count() as u32
 }
 
-fn part1_solution() -> u32 {
+fn part2_solution() -> u32 {
     let reports = parse_input();
 
-    reports.into_iter().filter(is_safe).count() as u32
+    reports
+        .into_iter()
+        .filter(|report| PUZZLE_CHECKER.is_safe_with_dampener(report, 1))
+        .count() as u32
 }
 
-fn part2_solution() -> u32 {
+// Usage: failures [--steps MIN-MAX] [--tolerance K]
+fn print_failures(args: &[String]) {
+    let mut checker = PUZZLE_CHECKER;
+    let mut tolerance = 0;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--steps" => {
+                let (min, max) = args
+                    .next()
+                    .and_then(|steps| steps.split_once('-'))
+                    .expect("Expected steps as MIN-MAX");
+                checker.steps = min.parse().unwrap()..=max.parse().unwrap();
+            }
+            "--tolerance" => {
+                let k = args.next().expect("Missing tolerance!");
+                tolerance = k.parse().expect("Cannot parse tolerance!");
+            }
+            _ => panic!("Unknown argument '{}'", arg),
+        }
+    }
+
     let reports = parse_input();
+    let mut num_failures = 0;
+    for (line, report) in reports.iter().enumerate() {
+        let Err(problem) = checker.check(report) else {
+            continue;
+        };
+        let to_drop = checker.levels_to_drop(report);
+        if to_drop.len() <= tolerance {
+            continue;
+        }
 
-    reports.into_iter().filter(is_safe_with_dampner).count() as u32
+        num_failures += 1;
+        println!(
+            "Report {}: {}: {} between levels {} and {}, drop {} to fix: {:?}",
+            line + 1,
+            report.iter().join(" "),
+            problem.reason,
+            problem.index,
+            problem.index + 1,
+            to_drop.len(),
+            to_drop
+        );
+    }
+    println!("{} unsafe reports", num_failures);
 }
 
 fn main() {
-    println!("{}", part1_solution());
-    println!("{}", part2_solution());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("failures") => print_failures(&args[1..]),
+        _ => {
+            println!("{}", part1_solution());
+            println!("{}", part2_solution());
+        }
+    }
 }
@@ -1,63 +1,212 @@
-use regex::Regex;
+use std::ops::Range;
 
 use aoc_rust_2024::io;
 
-fn part1_solution() -> u32 {
-    let input = io::get_puzzle_input(3);
+// Numbers in instructions have at most this many digits.
+const MAX_DIGITS: usize = 3;
 
-    let re = Regex::new(r"mul\((\d{1,3}),(\d{1,3})\)").unwrap();
+// A well formed instruction found in the corrupted memory, like `mul(2,4)`.
+#[derive(Debug, PartialEq, Eq)]
+struct Token<'a> {
+    name: &'a str,
+    args: Vec<u32>,
+    // Where the token sits in the memory, in bytes.
+    span: Range<usize>,
+}
 
-    let data: Vec<(u32, u32)> = re
-        .captures_iter(&input)
-        .map(|c| {
-            let (_, [x, y]) = c.extract();
-            (x.parse().unwrap(), y.parse().unwrap())
-        })
-        .collect();
+// Streams instructions out of corrupted memory, skipping over anything that
+// doesn't look like `name(arg,...)` for one of the given instruction names.
+struct Tokenizer<'a> {
+    memory: &'a str,
+    names: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(memory: &'a str, names: Vec<&'a str>) -> Self {
+        Self {
+            memory,
+            names,
+            pos: 0,
+        }
+    }
 
-    data.iter().map(|(x, y)| x * y).sum()
+    // Parse `(arg,...)` starting at `start`, returning the arguments and the
+    // end of the closing bracket.
+    fn parse_args(&self, start: usize) -> Option<(Vec<u32>, usize)> {
+        let bytes = self.memory.as_bytes();
+        let mut i = start;
+        if bytes.get(i) != Some(&b'(') {
+            return None;
+        }
+        i += 1;
+        if bytes.get(i) == Some(&b')') {
+            return Some((vec![], i + 1));
+        }
+
+        let mut args = vec![];
+        loop {
+            let digits = bytes[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+            if digits == 0 || digits > MAX_DIGITS {
+                return None;
+            }
+            args.push(self.memory[i..i + digits].parse().unwrap());
+            i += digits;
+
+            match bytes.get(i) {
+                Some(b',') => i += 1,
+                Some(b')') => return Some((args, i + 1)),
+                _ => return None,
+            }
+        }
+    }
 }
 
-fn part2_solution() -> u32 {
-    let input = io::get_puzzle_input(3);
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.memory.len() {
+            let start = self.pos;
+            self.pos += 1;
+
+            for &name in &self.names {
+                if !self.memory.as_bytes()[start..].starts_with(name.as_bytes()) {
+                    continue;
+                }
+                if let Some((args, end)) = self.parse_args(start + name.len()) {
+                    self.pos = end;
+                    return Some(Token {
+                        name: &self.memory[start..start + name.len()],
+                        args,
+                        span: start..end,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
 
-    let re = Regex::new(r"do\(\)|don't\(\)|mul\(\d{1,3},\d{1,3}\)").unwrap();
+// A `mul` that the interpreter came across, and whether it counted.
+#[derive(Debug)]
+struct TraceEntry {
+    span: Range<usize>,
+    product: u64,
+    enabled: bool,
+}
 
-    // let tokens = many0(
-    //     alt(
-    //         parse_token,
+#[derive(Debug)]
+struct Machine {
+    enabled: bool,
+    total: u64,
+    trace: Vec<TraceEntry>,
+}
 
-    //     )
-    // );
+type Handler = fn(&mut Machine, &Token);
 
-    let tokens_iter = re.find_iter(&input).map(|m| m.as_str().to_string());
+// Runs the instructions it knows about and ignores everything else. Each
+// instruction has a name, the number of arguments it takes and what it does.
+struct Interpreter {
+    instructions: Vec<(&'static str, usize, Handler)>,
+}
 
-    let mut good_tokens = vec![];
-    let mut keep = true;
-    for token in tokens_iter {
-        match token.as_str() {
-            "don't()" => {
-                keep = false;
+impl Interpreter {
+    fn new() -> Self {
+        Self {
+            instructions: vec![],
+        }
+    }
+
+    fn with(mut self, name: &'static str, arity: usize, handler: Handler) -> Self {
+        self.instructions.push((name, arity, handler));
+        self
+    }
+
+    // Just multiplication.
+    fn part1() -> Self {
+        Self::new().with("mul", 2, |machine, token| {
+            let product = token.args.iter().map(|&x| x as u64).product();
+            if machine.enabled {
+                machine.total += product;
             }
-            "do()" => keep = true,
-            _ if keep => {
-                good_tokens.push(token);
+            machine.trace.push(TraceEntry {
+                span: token.span.clone(),
+                product,
+                enabled: machine.enabled,
+            });
+        })
+    }
+
+    // Multiplication that can be switched on and off.
+    fn part2() -> Self {
+        Self::part1()
+            .with("do", 0, |machine, _| machine.enabled = true)
+            .with("don't", 0, |machine, _| machine.enabled = false)
+    }
+
+    fn run(&self, memory: &str) -> Machine {
+        let mut machine = Machine {
+            enabled: true,
+            total: 0,
+            trace: vec![],
+        };
+
+        let names = self.instructions.iter().map(|&(name, _, _)| name).collect();
+        for token in Tokenizer::new(memory, names) {
+            let instruction = self
+                .instructions
+                .iter()
+                .find(|&&(name, arity, _)| name == token.name && arity == token.args.len());
+            if let Some((_, _, handler)) = instruction {
+                handler(&mut machine, &token);
             }
-            _ => {}
         }
+        machine
     }
+}
 
-    let re = Regex::new(r"mul\((\d{1,3}),(\d{1,3})\)").unwrap();
-    good_tokens
-        .into_iter()
-        .map(|s| {
-            let (_, [x, y]) = re.captures(&s).unwrap().extract();
-            x.parse::<u32>().unwrap() * y.parse::<u32>().unwrap()
-        })
-        .sum()
+fn part1_solution() -> u64 {
+    let input = io::get_puzzle_input(3);
+    Interpreter::part1().run(&input).total
+}
+
+fn part2_solution() -> u64 {
+    let input = io::get_puzzle_input(3);
+    Interpreter::part2().run(&input).total
+}
+
+// Usage: trace [--part 1|2]
+fn print_trace(args: &[String]) {
+    let interpreter = match args {
+        [] => Interpreter::part2(),
+        [flag, part] if flag == "--part" && part == "1" => Interpreter::part1(),
+        [flag, part] if flag == "--part" && part == "2" => Interpreter::part2(),
+        _ => panic!("Unknown arguments {:?}", args),
+    };
+
+    let input = io::get_puzzle_input(3);
+    let machine = interpreter.run(&input);
+    for entry in &machine.trace {
+        println!(
+            "{}..{}\t{}\t{}\t{}",
+            entry.span.start,
+            entry.span.end,
+            &input[entry.span.clone()],
+            entry.product,
+            if entry.enabled { "enabled" } else { "disabled" }
+        );
+    }
+    println!("Total: {}", machine.total);
 }
 
 fn main() {
-    println!("{}", part1_solution());
-    println!("{}", part2_solution());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("trace") => print_trace(&args[1..]),
+        _ => {
+            println!("{}", part1_solution());
+            println!("{}", part2_solution());
+        }
+    }
 }
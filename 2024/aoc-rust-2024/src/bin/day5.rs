@@ -1,6 +1,8 @@
-use std::collections::{HashMap, HashSet};
-
-use aoc_rust_2024::io;
+use aoc_rust_2024::{
+    io,
+    order::{Cycle, PartialOrder},
+};
+use itertools::Itertools;
 
 type PageOrder = (u8, u8);
 type Update = Vec<u8>;
@@ -25,95 +27,79 @@ fn parsed_input() -> (Vec<PageOrder>, Vec<Update>) {
     (orderings, updates)
 }
 
-fn is_update_in_order(update: &Update, successors: &HashMap<u8, HashSet<u8>>) -> bool {
-    let mut seen = HashSet::new();
-    for &page in update {
-        if seen.intersection(&successors[&page]).next().is_some() {
-            return false;
-        }
-        seen.insert(page);
-    }
-    true
-}
-
-fn get_successors(orderings: Vec<PageOrder>) -> HashMap<u8, HashSet<u8>> {
-    orderings.into_iter().fold(
-        HashMap::<u8, HashSet<u8>>::new(),
-        |mut orderings, (before, after)| {
-            orderings.entry(before).or_default().insert(after);
-            orderings
-        },
-    )
-}
-
-fn fix_update_ordering(update: Update, successors: &HashMap<u8, HashSet<u8>>) -> Update {
-    let update: HashSet<_> = update.clone().into_iter().collect();
-    let successors: HashMap<u8, HashSet<u8>> = update
-        .clone()
-        .into_iter()
-        .map(|page| {
-            (
-                page,
-                successors[&page].intersection(&update).cloned().collect(),
-            )
-        })
-        .collect();
-
-    let mut dependencies: HashMap<u8, HashSet<u8>> =
-        update.iter().map(|&page| (page, HashSet::new())).collect();
-    for (&before, after) in successors.iter() {
-        for after in after {
-            dependencies.get_mut(after).unwrap().insert(before);
-        }
-    }
-
-    let mut dependency_free_pages: Vec<u8> = dependencies
-        .iter()
-        .filter(|(_, deps)| deps.is_empty())
-        .map(|(&page, _)| page)
-        .collect();
-
-    let mut in_order = vec![];
-    while let Some(page) = dependency_free_pages.pop() {
-        in_order.push(page);
-        for &later_page in &successors[&page] {
-            let deps = dependencies.get_mut(&later_page).unwrap();
-            deps.remove(&page);
-            if deps.is_empty() {
-                dependency_free_pages.push(later_page);
-            }
-        }
-    }
-
-    in_order
+fn fix_update_ordering(update: &Update, order: &PartialOrder<u8>) -> Update {
+    order.sort(update).unwrap_or_else(|Cycle(pages)| {
+        panic!("Cannot fix {:?}, the rules loop round {:?}", update, pages)
+    })
 }
 
 fn part1_solution() -> u32 {
     let (orderings, updates) = parsed_input();
-
-    let successors = get_successors(orderings);
+    let order = PartialOrder::new(orderings);
 
     updates
         .into_iter()
-        .filter(|update| is_update_in_order(update, &successors))
+        .filter(|update| order.is_ordered(update))
         .map(|update| update[update.len() / 2] as u32)
         .sum()
 }
 
 fn part2_solution() -> u32 {
     let (orderings, updates) = parsed_input();
-
-    let successors = get_successors(orderings);
+    let order = PartialOrder::new(orderings);
 
     updates
         .into_iter()
-        .filter(|update| !is_update_in_order(update, &successors))
-        .map(|update| fix_update_ordering(update, &successors))
+        .filter(|update| !order.is_ordered(update))
+        .map(|update| fix_update_ordering(&update, &order))
         .map(|update| update[update.len() / 2] as u32)
         .sum()
 }
 
+fn print_check() {
+    let (orderings, updates) = parsed_input();
+    let order = PartialOrder::new(orderings);
+
+    for update in &updates {
+        let pages = update.iter().join(",");
+        match order.first_violation(update) {
+            None => println!("{}: in order", pages),
+            Some((before, after)) => match order.sort(update) {
+                Ok(fixed) => println!(
+                    "{}: breaks {}|{}, should be {}",
+                    pages,
+                    before,
+                    after,
+                    fixed.iter().join(",")
+                ),
+                Err(Cycle(cycle)) => println!(
+                    "{}: breaks {}|{}, cannot be fixed as the rules loop round {}",
+                    pages,
+                    before,
+                    after,
+                    cycle.iter().join(",")
+                ),
+            },
+        }
+    }
+
+    match order.is_total_order() {
+        Ok(true) => println!("The rules put all the pages in a single order"),
+        Ok(false) => println!("The rules leave some pages unordered"),
+        Err(Cycle(cycle)) => println!(
+            "The rules contradict themselves: {}",
+            cycle.iter().join(" before ")
+        ),
+    }
+}
+
 fn main() {
-    println!("{}", part1_solution());
-    println!("{}", part2_solution());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("check") => print_check(),
+        _ => {
+            println!("{}", part1_solution());
+            println!("{}", part2_solution());
+        }
+    }
 }
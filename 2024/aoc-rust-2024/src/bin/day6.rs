@@ -1,5 +1,7 @@
-use aoc_rust_2024::io;
-use std::collections::HashSet;
+use std::collections::HashMap;
+
+use aoc_rust_2024::{grid::GridBitSet, io};
+use rayon::prelude::*;
 use Direction::*;
 
 type Position = (usize, usize);
@@ -12,17 +14,6 @@ enum Direction {
     West,
 }
 
-#[derive(Debug)]
-struct Map {
-    data: Vec<Vec<char>>,
-    start_position: Position,
-    start_direction: Direction,
-    curr_position: Option<Position>,
-    curr_direction: Direction,
-    width: usize,
-    height: usize,
-}
-
 impl Direction {
     fn turn_90_right(&self) -> Self {
         match self {
@@ -34,6 +25,35 @@ impl Direction {
     }
 }
 
+// Everything the guard needs to know to keep walking.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Guard {
+    position: Position,
+    direction: Direction,
+}
+
+// The guard's route, one state per position visited. If the guard gets stuck
+// in a loop, the route stops just before the first repeated state and
+// `cycle_start` is where the repeating part begins.
+struct Trace {
+    route: Vec<Guard>,
+    cycle_start: Option<usize>,
+}
+
+// The lab itself. This never changes, so it can be shared between any number
+// of guards.
+#[derive(Debug)]
+struct Map {
+    obstacles: Vec<Vec<bool>>,
+    start: Guard,
+    width: usize,
+    height: usize,
+    // For each direction and each position, the row (North/South) or column
+    // (East/West) where a guard walking that way stops in front of the next
+    // obstacle, or None if they walk off the map.
+    jumps: [Vec<Vec<Option<usize>>>; 4],
+}
+
 impl Map {
     pub fn new(input: &str) -> Self {
         let data: Vec<Vec<_>> = input.lines().map(|line| line.chars().collect()).collect();
@@ -49,107 +69,304 @@ impl Map {
             }
         }
 
+        let obstacles: Vec<Vec<bool>> = data
+            .iter()
+            .map(|row| row.iter().map(|&c| c == '#').collect())
+            .collect();
+
+        let mut jumps: [Vec<Vec<Option<usize>>>; 4] =
+            std::array::from_fn(|_| vec![vec![None; width]; height]);
+        for row in 0..height {
+            let mut stop = None;
+            for col in 0..width {
+                if obstacles[row][col] {
+                    stop = Some(col + 1);
+                }
+                jumps[West as usize][row][col] = stop;
+            }
+            let mut stop = None;
+            for col in (0..width).rev() {
+                if obstacles[row][col] {
+                    stop = col.checked_sub(1);
+                }
+                jumps[East as usize][row][col] = stop;
+            }
+        }
+        for col in 0..width {
+            let mut stop = None;
+            for row in 0..height {
+                if obstacles[row][col] {
+                    stop = Some(row + 1);
+                }
+                jumps[North as usize][row][col] = stop;
+            }
+            let mut stop = None;
+            for row in (0..height).rev() {
+                if obstacles[row][col] {
+                    stop = row.checked_sub(1);
+                }
+                jumps[South as usize][row][col] = stop;
+            }
+        }
+
         Self {
-            data,
-            start_position: start_pos,
-            start_direction: North,
-            curr_position: Some(start_pos),
-            curr_direction: North,
+            obstacles,
+            start: Guard {
+                position: start_pos,
+                direction: North,
+            },
             height,
             width,
+            jumps,
         }
     }
 
-    pub fn reset(&mut self) {
-        self.curr_direction = self.start_direction;
-        self.curr_position = Some(self.start_position);
+    fn is_obstacle(&self, pos: Position, obstruction: Option<Position>) -> bool {
+        self.obstacles[pos.0][pos.1] || obstruction == Some(pos)
     }
 
-    pub fn take_step(&mut self) -> Option<Position> {
-        if let Some(pos) = self.curr_position {
-            let next_pos_unchecked = |direction: Direction| match direction {
-                North if pos.0 > 0 => Some((pos.0 - 1, pos.1)),
-                North if pos.0 == 0 => None,
-                South if pos.0 < self.height - 1 => Some((pos.0 + 1, pos.1)),
-                South if pos.0 >= self.height - 1 => None,
-                West if pos.1 > 0 => Some((pos.0, pos.1 - 1)),
-                West if pos.1 == 0 => None,
-                East if pos.1 < self.width - 1 => Some((pos.0, pos.1 + 1)),
-                East if pos.1 >= self.width - 1 => None,
-                _ => panic!(),
-            };
-
-            let mut dir = self.curr_direction;
-            loop {
-                let new_pos = next_pos_unchecked(dir);
-                match new_pos {
-                    None => {
-                        self.curr_direction = dir;
-                        self.curr_position = None;
-                        break;
-                    }
-                    Some((row, col)) => {
-                        if self.data[row][col] != '#' {
-                            self.curr_direction = dir;
-                            self.curr_position = new_pos;
-                            break;
-                        } else {
-                            dir = dir.turn_90_right();
-                        }
-                    }
-                }
+    // Move the guard forward one position, turning first if they're blocked.
+    // Returns None once the guard leaves the map.
+    pub fn take_step(&self, guard: Guard, obstruction: Option<Position>) -> Option<Guard> {
+        let pos = guard.position;
+        let next_pos_unchecked = |direction: Direction| match direction {
+            North if pos.0 > 0 => Some((pos.0 - 1, pos.1)),
+            South if pos.0 < self.height - 1 => Some((pos.0 + 1, pos.1)),
+            West if pos.1 > 0 => Some((pos.0, pos.1 - 1)),
+            East if pos.1 < self.width - 1 => Some((pos.0, pos.1 + 1)),
+            _ => None,
+        };
+
+        let mut direction = guard.direction;
+        loop {
+            let position = next_pos_unchecked(direction)?;
+            if !self.is_obstacle(position, obstruction) {
+                return Some(Guard {
+                    position,
+                    direction,
+                });
             }
+            direction = direction.turn_90_right();
         }
-        self.curr_position
     }
-}
 
-fn part1_solution() -> u32 {
-    let input = io::get_puzzle_input(6);
-    let mut map = Map::new(&input);
-    let mut visited = HashSet::new();
+    // Move the guard straight up to the next obstacle and turn them to face
+    // right. Returns None if the guard walks off the map instead.
+    fn jump(&self, guard: Guard, obstruction: Option<Position>) -> Option<Guard> {
+        let (row, col) = guard.position;
+        let stop = self.jumps[guard.direction as usize][row][col];
+
+        // The extra obstruction is only in the way if it is ahead of the
+        // guard and closer than the obstacle they would otherwise stop at.
+        let stop = match (guard.direction, obstruction) {
+            (North, Some((o_row, o_col))) if o_col == col && o_row < row => {
+                Some(stop.map_or(o_row + 1, |stop| stop.max(o_row + 1)))
+            }
+            (South, Some((o_row, o_col))) if o_col == col && o_row > row => {
+                Some(stop.map_or(o_row - 1, |stop| stop.min(o_row - 1)))
+            }
+            (West, Some((o_row, o_col))) if o_row == row && o_col < col => {
+                Some(stop.map_or(o_col + 1, |stop| stop.max(o_col + 1)))
+            }
+            (East, Some((o_row, o_col))) if o_row == row && o_col > col => {
+                Some(stop.map_or(o_col - 1, |stop| stop.min(o_col - 1)))
+            }
+            _ => stop,
+        }?;
 
-    visited.insert(map.curr_position.unwrap());
-    while let Some(pos) = map.take_step() {
-        visited.insert(pos);
+        let position = match guard.direction {
+            North | South => (stop, col),
+            East | West => (row, stop),
+        };
+        Some(Guard {
+            position,
+            direction: guard.direction.turn_90_right(),
+        })
     }
 
-    visited.len() as u32
+    // Jump from obstacle to obstacle, looking for a turn we've already made.
+    fn is_loop(&self, obstruction: Position, seen: &mut [GridBitSet]) -> bool {
+        seen.iter_mut().for_each(GridBitSet::clear);
+
+        let mut guard = self.start;
+        while let Some(next) = self.jump(guard, Some(obstruction)) {
+            guard = next;
+            if !seen[guard.direction as usize].insert(guard.position) {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn patrolled_positions(&self) -> GridBitSet {
+        let mut visited = GridBitSet::new(self.height, self.width);
+        let mut guard = self.start;
+
+        visited.insert(guard.position);
+        while let Some(next) = self.take_step(guard, None) {
+            guard = next;
+            visited.insert(guard.position);
+        }
+        visited
+    }
+
+    fn trace(&self, obstruction: Option<Position>) -> Trace {
+        let mut seen = HashMap::new();
+        let mut route = vec![];
+
+        let mut guard = Some(self.start);
+        while let Some(g) = guard {
+            if let Some(&i) = seen.get(&g) {
+                return Trace {
+                    route,
+                    cycle_start: Some(i),
+                };
+            }
+            seen.insert(g, route.len());
+            route.push(g);
+            guard = self.take_step(g, obstruction);
+        }
+
+        Trace {
+            route,
+            cycle_start: None,
+        }
+    }
+
+    // Draw part of the guard's route like the puzzle description: '|' and
+    // '-' where the guard walks straight, and '+' where they turn or cross
+    // their own path. `next` gives the state after the last one in `route`.
+    fn render(
+        &self,
+        route: &[Guard],
+        next: Option<Guard>,
+        obstruction: Option<Position>,
+    ) -> String {
+        let mut vertical = GridBitSet::new(self.height, self.width);
+        let mut horizontal = GridBitSet::new(self.height, self.width);
+
+        let following = route.iter().skip(1).copied().map(Some).chain([next]);
+        for (guard, next) in route.iter().zip(following) {
+            let directions = [Some(guard.direction), next.map(|g| g.direction)];
+            for direction in directions.into_iter().flatten() {
+                match direction {
+                    North | South => vertical.insert(guard.position),
+                    East | West => horizontal.insert(guard.position),
+                };
+            }
+        }
+
+        (0..self.height)
+            .map(|row| {
+                (0..self.width)
+                    .map(|col| {
+                        let pos = (row, col);
+                        if self.obstacles[row][col] {
+                            '#'
+                        } else if obstruction == Some(pos) {
+                            'O'
+                        } else if pos == self.start.position && route.first() == Some(&self.start) {
+                            '^'
+                        } else {
+                            match (vertical.contains(pos), horizontal.contains(pos)) {
+                                (true, true) => '+',
+                                (true, false) => '|',
+                                (false, true) => '-',
+                                (false, false) => '.',
+                            }
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // Every position where a new obstruction traps the guard in a loop. Only
+    // positions on the guard's original route can change where they go.
+    fn loop_obstructions(&self) -> Vec<Position> {
+        let candidates: Vec<Position> = self
+            .patrolled_positions()
+            .iter()
+            .filter(|&pos| pos != self.start.position)
+            .collect();
+
+        candidates
+            .into_par_iter()
+            .map_init(
+                || vec![GridBitSet::new(self.height, self.width); 4],
+                |seen, pos| (pos, self.is_loop(pos, seen)),
+            )
+            .filter(|&(_, is_loop)| is_loop)
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+}
+
+fn part1_solution() -> u32 {
+    let input = io::get_puzzle_input(6);
+    let map = Map::new(&input);
+    map.patrolled_positions().len() as u32
 }
 
 fn part2_solution() -> u32 {
     let input = io::get_puzzle_input(6);
-    let mut map = Map::new(&input);
-    let mut visited = HashSet::new();
+    let map = Map::new(&input);
+    map.loop_obstructions().len() as u32
+}
+
+fn print_loop_obstructions() {
+    let input = io::get_puzzle_input(6);
+    let map = Map::new(&input);
 
-    while let Some(pos) = map.take_step() {
-        visited.insert(pos);
+    let obstructions = map.loop_obstructions();
+    for (row, col) in &obstructions {
+        println!("{},{}", row, col);
     }
-    map.reset();
-
-    let obstruction_causes_cycle = |obstruction_pos: &Position| -> bool {
-        let &(row, col) = obstruction_pos;
-        map.data[row][col] = '#';
-
-        let mut visited_with_obstruction = HashSet::new();
-        let mut cycle_detected = false;
-        while let Some(pos) = map.take_step() {
-            if visited_with_obstruction.contains(&(pos, map.curr_direction)) {
-                cycle_detected = true;
-                break;
-            }
-            visited_with_obstruction.insert((pos, map.curr_direction));
-        }
+    println!("{} positions cause a loop", obstructions.len());
+}
+
+// Usage: trace [ROW,COL]
+fn print_trace(args: &[String]) {
+    let input = io::get_puzzle_input(6);
+    let map = Map::new(&input);
 
-        map.data[row][col] = '.';
-        map.reset();
-        cycle_detected
-    };
+    let obstruction = args.first().map(|arg| {
+        let (row, col) = arg
+            .split_once(',')
+            .expect("Expected obstruction as ROW,COL");
+        (row.parse().unwrap(), col.parse().unwrap())
+    });
+    let Trace { route, cycle_start } = map.trace(obstruction);
 
-    visited.into_iter().filter(obstruction_causes_cycle).count() as u32
+    match cycle_start {
+        None => {
+            println!("{}", map.render(&route, None, obstruction));
+            println!("The guard leaves the map after {} steps", route.len() - 1);
+        }
+        Some(i) => {
+            println!("Lead-in:");
+            println!("{}", map.render(&route[..i], Some(route[i]), obstruction));
+            println!();
+            println!("Loop:");
+            println!("{}", map.render(&route[i..], Some(route[i]), obstruction));
+            println!(
+                "The guard walks {} steps before getting stuck in a loop of {} steps",
+                i,
+                route.len() - i
+            );
+        }
+    }
 }
 
 fn main() {
-    println!("{}", part1_solution());
-    println!("{}", part2_solution());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("loops") => print_loop_obstructions(),
+        Some("trace") => print_trace(&args[1..]),
+        _ => {
+            println!("{}", part1_solution());
+            println!("{}", part2_solution());
+        }
+    }
 }
@@ -1,40 +1,252 @@
 use aoc_rust_2024::io;
+use itertools::Itertools;
+
+// The values of x that give x op y = z.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Inverse {
+    Exactly(i64),
+    // Any x at all, e.g. x * 0 = 0.
+    Anything,
+}
+
+// An operator for calibration equations, which are always evaluated left to
+// right. Both forms return None if there's no answer, or it doesn't fit in an
+// i64.
+trait Operator {
+    fn symbol(&self) -> String;
+
+    // x op y
+    fn apply(&self, x: i64, y: i64) -> Option<i64>;
+
+    // Every x with x op y = z. Operators where more than one x works, but
+    // not all of them, can't be solved backwards.
+    fn invert(&self, z: i64, y: i64) -> Option<Inverse>;
+}
+
+struct Add;
+
+impl Operator for Add {
+    fn symbol(&self) -> String {
+        "+".to_string()
+    }
+
+    fn apply(&self, x: i64, y: i64) -> Option<i64> {
+        x.checked_add(y)
+    }
+
+    fn invert(&self, z: i64, y: i64) -> Option<Inverse> {
+        z.checked_sub(y).map(Inverse::Exactly)
+    }
+}
+
+struct Sub;
+
+impl Operator for Sub {
+    fn symbol(&self) -> String {
+        "-".to_string()
+    }
+
+    fn apply(&self, x: i64, y: i64) -> Option<i64> {
+        x.checked_sub(y)
+    }
+
+    fn invert(&self, z: i64, y: i64) -> Option<Inverse> {
+        z.checked_add(y).map(Inverse::Exactly)
+    }
+}
+
+struct Mul;
+
+impl Operator for Mul {
+    fn symbol(&self) -> String {
+        "*".to_string()
+    }
+
+    fn apply(&self, x: i64, y: i64) -> Option<i64> {
+        x.checked_mul(y)
+    }
+
+    // Multiplying by zero loses x entirely, so anything goes if z is 0 too.
+    fn invert(&self, z: i64, y: i64) -> Option<Inverse> {
+        match y {
+            0 => (z == 0).then_some(Inverse::Anything),
+            _ => (z.checked_rem(y)? == 0).then_some(Inverse::Exactly(z.checked_div(y)?)),
+        }
+    }
+}
+
+struct Xor;
+
+impl Operator for Xor {
+    fn symbol(&self) -> String {
+        "^".to_string()
+    }
+
+    fn apply(&self, x: i64, y: i64) -> Option<i64> {
+        Some(x ^ y)
+    }
+
+    fn invert(&self, z: i64, y: i64) -> Option<Inverse> {
+        Some(Inverse::Exactly(z ^ y))
+    }
+}
+
+// Writes the digits of y after the digits of x, in the given base.
+struct Concat {
+    base: i64,
+}
+
+impl Concat {
+    // base^(number of digits in y)
+    fn shift(&self, y: i64) -> Option<i64> {
+        let mut shift = self.base;
+        let mut rest = y / self.base;
+        while rest > 0 {
+            shift = shift.checked_mul(self.base)?;
+            rest /= self.base;
+        }
+        Some(shift)
+    }
+}
+
+impl Operator for Concat {
+    fn symbol(&self) -> String {
+        match self.base {
+            10 => "||".to_string(),
+            base => format!("||{}", base),
+        }
+    }
+
+    fn apply(&self, x: i64, y: i64) -> Option<i64> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        x.checked_mul(self.shift(y)?)?.checked_add(y)
+    }
+
+    // Strip the digits of y off the end of z.
+    fn invert(&self, z: i64, y: i64) -> Option<Inverse> {
+        if z < 0 || y < 0 {
+            return None;
+        }
+        let shift = self.shift(y)?;
+        (z % shift == y).then(|| Inverse::Exactly(z / shift))
+    }
+}
+
+// Operators by symbol: + - * ^ || and ||N for concatenation in base N.
+fn parse_operator(symbol: &str) -> Box<dyn Operator> {
+    match symbol {
+        "+" => Box::new(Add),
+        "-" => Box::new(Sub),
+        "*" => Box::new(Mul),
+        "^" => Box::new(Xor),
+        "||" => Box::new(Concat { base: 10 }),
+        _ => match symbol.strip_prefix("||").and_then(|base| base.parse().ok()) {
+            Some(base) if base >= 2 => Box::new(Concat { base }),
+            _ => panic!("Unknown operator '{}'", symbol),
+        },
+    }
+}
 
 struct Equation {
     total: i64,
     operands: Vec<i64>,
 }
 
-type Operation = fn(i64, i64) -> i64;
-
 impl Equation {
-    fn has_valid_operation(&self, ops: &[Operation]) -> bool {
-        // store (running_total, depth) on a stack
-        let mut stack = vec![(self.operands[0], 1)];
-        while let Some((total, depth)) = stack.pop() {
-            if depth == self.operands.len() {
-                if total == self.total {
-                    return true;
-                }
-            } else {
-                for op in ops {
-                    stack.push((op(total, self.operands[depth]), depth + 1));
-                }
+    // The operators to put between the operands, from left to right, to make
+    // the total. Works backwards from the total, undoing one operator at a
+    // time, which rules out most operators at each step straight away. This
+    // finds a solution whenever there is one, as long as each operator's
+    // `invert` gives every x that works, which is true of all of those from
+    // `parse_operator`.
+    fn solve<'a>(&self, ops: &'a [Box<dyn Operator>]) -> Option<Vec<&'a dyn Operator>> {
+        let mut solution = Vec::with_capacity(self.operands.len() - 1);
+        if self.solve_from(self.operands.len() - 1, self.total, ops, &mut solution) {
+            solution.reverse();
+            Some(solution)
+        } else {
+            None
+        }
+    }
+
+    // Whether the first `i + 1` operands can make `total`, pushing the
+    // operators used onto `solution` in reverse order.
+    fn solve_from<'a>(
+        &self,
+        i: usize,
+        total: i64,
+        ops: &'a [Box<dyn Operator>],
+        solution: &mut Vec<&'a dyn Operator>,
+    ) -> bool {
+        if i == 0 {
+            return total == self.operands[0];
+        }
+
+        for op in ops {
+            solution.push(op.as_ref());
+            let solved = match op.invert(total, self.operands[i]) {
+                Some(Inverse::Exactly(rest)) => self.solve_from(i - 1, rest, ops, solution),
+                Some(Inverse::Anything) => self.evaluate_any(i - 1, ops, solution),
+                None => false,
+            };
+            if solved {
+                return true;
             }
+            solution.pop();
         }
         false
     }
-}
 
-fn mul(x: i64, y: i64) -> i64 {
-    x * y
-}
-fn add(x: i64, y: i64) -> i64 {
-    x + y
-}
+    // Whether the first `i + 1` operands make anything at all, i.e. some
+    // choice of operators doesn't overflow or hit a value an operator can't
+    // take. Pushes the operators used onto `solution` in reverse order, like
+    // `solve_from`.
+    fn evaluate_any<'a>(
+        &self,
+        i: usize,
+        ops: &'a [Box<dyn Operator>],
+        solution: &mut Vec<&'a dyn Operator>,
+    ) -> bool {
+        fn forward<'a>(
+            operands: &[i64],
+            total: i64,
+            ops: &'a [Box<dyn Operator>],
+            used: &mut Vec<&'a dyn Operator>,
+        ) -> bool {
+            let Some((&next, rest)) = operands.split_first() else {
+                return true;
+            };
+            for op in ops {
+                let Some(total) = op.apply(total, next) else {
+                    continue;
+                };
+                used.push(op.as_ref());
+                if forward(rest, total, ops, used) {
+                    return true;
+                }
+                used.pop();
+            }
+            false
+        }
 
-fn concat(x: i64, y: i64) -> i64 {
-    format!("{x}{y}").parse().unwrap()
+        let mut used = vec![];
+        if !forward(&self.operands[1..=i], self.operands[0], ops, &mut used) {
+            return false;
+        }
+        solution.extend(used.into_iter().rev());
+        true
+    }
+
+    fn evaluate(&self, solution: &[&dyn Operator]) -> Option<i64> {
+        self.operands[1..]
+            .iter()
+            .zip(solution)
+            .try_fold(self.operands[0], |total, (&operand, op)| {
+                op.apply(total, operand)
+            })
+    }
 }
 
 fn parse_input() -> Vec<Equation> {
@@ -57,27 +269,56 @@ fn parse_input() -> Vec<Equation> {
         .collect()
 }
 
-fn part1_solution() -> i64 {
-    let data = parse_input();
-    let ops = [add, mul];
-
-    data.into_iter()
-        .filter(|eq| eq.has_valid_operation(&ops))
+fn calibration_result(ops: &[Box<dyn Operator>]) -> i64 {
+    parse_input()
+        .into_iter()
+        .filter(|eq| eq.solve(ops).is_some())
         .map(|eq| eq.total)
         .sum()
 }
 
+fn part1_solution() -> i64 {
+    calibration_result(&[Box::new(Add), Box::new(Mul)])
+}
+
 fn part2_solution() -> i64 {
-    let data = parse_input();
-    let ops = [add, mul, concat];
+    calibration_result(&[Box::new(Add), Box::new(Mul), Box::new(Concat { base: 10 })])
+}
 
-    data.into_iter()
-        .filter(|eq| eq.has_valid_operation(&ops))
-        .map(|eq| eq.total)
-        .sum()
+// Usage: solve [OPERATOR...]
+fn print_solutions(args: &[String]) {
+    let symbols = if args.is_empty() {
+        vec!["+", "*", "||"]
+    } else {
+        args.iter().map(String::as_str).collect()
+    };
+    let ops = symbols.into_iter().map(parse_operator).collect_vec();
+
+    let mut result = 0;
+    for eq in parse_input() {
+        let Some(solution) = eq.solve(&ops) else {
+            continue;
+        };
+        assert_eq!(eq.evaluate(&solution), Some(eq.total));
+
+        let expression = eq.operands[1..]
+            .iter()
+            .zip(&solution)
+            .map(|(operand, op)| format!(" {} {}", op.symbol(), operand))
+            .join("");
+        println!("{} = {}{}", eq.total, eq.operands[0], expression);
+        result += eq.total;
+    }
+    println!("Total calibration result: {}", result);
 }
 
 fn main() {
-    println!("{}", part1_solution());
-    println!("{}", part2_solution());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("solve") => print_solutions(&args[1..]),
+        _ => {
+            println!("{}", part1_solution());
+            println!("{}", part2_solution());
+        }
+    }
 }
@@ -1,64 +1,141 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap};
 
 use aoc_rust_2024::io;
+use itertools::Itertools;
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Position {
     x: i32,
     y: i32,
 }
 
+#[derive(Debug)]
 struct Antenna {
     position: Position,
-    _frequency: char,
+    frequency: char,
 }
 
-impl Antenna {
-    fn get_antinode_positions(&self, other: &Self) -> Option<(Position, Position)> {
-        let dy = other.position.y - self.position.y;
-        let dx = other.position.x - self.position.x;
-
-        Some((
-            Position {
-                x: self.position.x + 2 * dx,
-                y: self.position.y + 2 * dy,
-            },
-            Position {
-                x: self.position.x - dx,
-                y: self.position.y - dy,
-            },
-        ))
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
     }
+}
 
-    fn get_antinode_positions_with_harmonics(
-        &self,
-        other: &Self,
-        bounds: (usize, usize),
-    ) -> HashSet<Position> {
-        let (height, width) = bounds;
-        let dy = other.position.y - self.position.y;
-        let dx = other.position.x - self.position.x;
-
-        let is_pos_in_bounds =
-            |(x, y): (i32, i32)| x >= 0 && x < width as i32 && y >= 0 && y < height as i32;
-
-        let mut positions = HashSet::new();
-
-        // walk forward
-        let mut pos = (self.position.x, self.position.y);
-        while is_pos_in_bounds(pos) {
-            positions.insert(Position { x: pos.0, y: pos.1 });
-            pos = (pos.0 + dx, pos.1 + dy);
+// Which points in line with a pair of antennas are antinodes.
+#[derive(Clone, Debug)]
+struct Harmonics {
+    // A point is an antinode if it is exactly one of these times as far from
+    // one antenna as from the other. None means every point in line counts.
+    ratios: Option<Vec<i32>>,
+    // How far (in Manhattan distance) an antinode can be from the nearer
+    // antenna of the pair.
+    max_distance: Option<i32>,
+    // The grid is (height, width).
+    size: (usize, usize),
+}
+
+impl Harmonics {
+    fn puzzle(size: (usize, usize)) -> Self {
+        Self {
+            ratios: Some(vec![2]),
+            max_distance: None,
+            size,
         }
+    }
+
+    fn puzzle_with_harmonics(size: (usize, usize)) -> Self {
+        Self {
+            ratios: None,
+            ..Self::puzzle(size)
+        }
+    }
 
-        // walk backward
-        let mut pos = (self.position.x, self.position.y);
-        while is_pos_in_bounds(pos) {
-            positions.insert(Position { x: pos.0, y: pos.1 });
-            pos = (pos.0 - dx, pos.1 - dy);
+    fn in_bounds(&self, pos: Position) -> bool {
+        let (height, width) = self.size;
+        (0..width as i32).contains(&pos.x) && (0..height as i32).contains(&pos.y)
+    }
+
+    // Points in line with `a` and `b` are a + k*step for integer k, with `b`
+    // at k = steps_apart. Using the smallest such step means that no point in
+    // line is skipped over.
+    fn antinodes(&self, a: Position, b: Position) -> Vec<Position> {
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let steps_apart = gcd(dx, dy);
+        let step = (dx / steps_apart, dy / steps_apart);
+        let step_len = step.0.abs() + step.1.abs();
+
+        let is_antinode = |k: i32| {
+            let (to_a, to_b) = (k.abs(), (k - steps_apart).abs());
+            let in_range = self
+                .max_distance
+                .is_none_or(|d| to_a.min(to_b) * step_len <= d);
+            let in_ratio = self
+                .ratios
+                .as_ref()
+                .is_none_or(|ratios| ratios.iter().any(|&r| to_a == r * to_b || to_b == r * to_a));
+            in_range && in_ratio
+        };
+
+        self.steps_on_grid(a, step)
+            .filter(|&k| is_antinode(k))
+            .map(|k| Position {
+                x: a.x + k * step.0,
+                y: a.y + k * step.1,
+            })
+            .collect()
+    }
+
+    // The values of k for which pos + k*step is on the grid.
+    fn steps_on_grid(&self, pos: Position, step: (i32, i32)) -> std::ops::RangeInclusive<i32> {
+        let (height, width) = self.size;
+        // Need 0 <= p + k*s < len. Stepping backwards is the same as
+        // stepping forwards on the grid flipped over.
+        let axis_range = |p: i32, s: i32, len: i32| match s.signum() {
+            0 if (0..len).contains(&p) => (i32::MIN, i32::MAX),
+            0 => (1, 0),
+            1 => (-(p.div_euclid(s)), (len - 1 - p).div_euclid(s)),
+            _ => (-((len - 1 - p).div_euclid(-s)), p.div_euclid(-s)),
+        };
+        let (x_lo, x_hi) = axis_range(pos.x, step.0, width as i32);
+        let (y_lo, y_hi) = axis_range(pos.y, step.1, height as i32);
+        x_lo.max(y_lo)..=x_hi.min(y_hi)
+    }
+
+    // Every antinode, with the pairs of antennas that produce it.
+    fn sources(
+        &self,
+        antennas: &HashMap<char, Vec<Antenna>>,
+    ) -> BTreeMap<Position, Vec<(Position, Position)>> {
+        let mut sources: BTreeMap<Position, Vec<_>> = BTreeMap::new();
+        for antennas in antennas.values() {
+            for (a, b) in antennas.iter().tuple_combinations() {
+                for antinode in self.antinodes(a.position, b.position) {
+                    sources
+                        .entry(antinode)
+                        .or_default()
+                        .push((a.position, b.position));
+                }
+            }
         }
+        sources
+    }
 
-        positions
+    fn render(&self, antennas: &HashMap<char, Vec<Antenna>>) -> String {
+        let (height, width) = self.size;
+        let mut grid = vec![vec!['.'; width]; height];
+        for antinode in self.sources(antennas).keys() {
+            grid[antinode.y as usize][antinode.x as usize] = '#';
+        }
+        for antenna in antennas.values().flatten() {
+            if self.in_bounds(antenna.position) {
+                grid[antenna.position.y as usize][antenna.position.x as usize] = antenna.frequency;
+            }
+        }
+        grid.iter()
+            .map(|row| row.iter().collect::<String>())
+            .join("\n")
     }
 }
 
@@ -78,7 +155,7 @@ fn parse_input() -> (HashMap<char, Vec<Antenna>>, (usize, usize)) {
                         x: col as i32,
                         y: row as i32,
                     },
-                    _frequency: c,
+                    frequency: c,
                 });
             }
         }
@@ -87,43 +164,74 @@ fn parse_input() -> (HashMap<char, Vec<Antenna>>, (usize, usize)) {
 }
 
 fn part1_solution() -> usize {
-    let (mut antennas, (height, width)) = parse_input();
-
-    let mut antinodes = HashSet::new();
-    for (_, antennas) in antennas.iter_mut() {
-        while let Some(antenna) = antennas.pop() {
-            for other in antennas.iter() {
-                let (antinode1, antinode2) = antenna.get_antinode_positions(other).unwrap();
-                antinodes.insert(antinode1);
-                antinodes.insert(antinode2);
-            }
-        }
-    }
-
-    antinodes
-        .into_iter()
-        .filter(|pos| (0..height as i32).contains(&pos.y) && (0..width as i32).contains(&pos.x))
-        .count()
+    let (antennas, size) = parse_input();
+    Harmonics::puzzle(size).sources(&antennas).len()
 }
 
 fn part2_solution() -> usize {
-    let (mut antennas, (height, width)) = parse_input();
-
-    let mut antinodes = HashSet::new();
-    for (_, antennas) in antennas.iter_mut() {
-        while let Some(antenna) = antennas.pop() {
-            for other in antennas.iter() {
-                let positions =
-                    antenna.get_antinode_positions_with_harmonics(other, (height, width));
-                antinodes.extend(positions);
+    let (antennas, size) = parse_input();
+    Harmonics::puzzle_with_harmonics(size)
+        .sources(&antennas)
+        .len()
+}
+
+// Usage: antinodes [--ratios R1,R2,...|any] [--max-distance D] [--size WxH] [--sources]
+fn print_antinodes(args: &[String]) {
+    let (antennas, size) = parse_input();
+    let mut harmonics = Harmonics::puzzle(size);
+    let mut show_sources = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ratios" => {
+                let ratios = args.next().expect("Missing ratios!");
+                harmonics.ratios = match ratios.as_str() {
+                    "any" => None,
+                    _ => Some(
+                        ratios
+                            .split(',')
+                            .map(|r| r.parse().expect("Cannot parse ratio!"))
+                            .collect(),
+                    ),
+                };
+            }
+            "--max-distance" => {
+                let distance = args.next().expect("Missing max distance!");
+                harmonics.max_distance = Some(distance.parse().expect("Cannot parse distance!"));
+            }
+            "--size" => {
+                let (width, height) = args
+                    .next()
+                    .and_then(|size| size.split_once('x'))
+                    .expect("Expected size as WxH");
+                harmonics.size = (height.parse().unwrap(), width.parse().unwrap());
             }
+            "--sources" => show_sources = true,
+            _ => panic!("Unknown argument '{}'", arg),
         }
     }
 
-    antinodes.len()
+    let sources = harmonics.sources(&antennas);
+    println!("{}", harmonics.render(&antennas));
+    if show_sources {
+        for (antinode, pairs) in &sources {
+            let pairs = pairs
+                .iter()
+                .map(|(a, b)| format!("({},{})-({},{})", a.x, a.y, b.x, b.y));
+            println!("({},{}) <- {}", antinode.x, antinode.y, pairs.format(" "));
+        }
+    }
+    println!("{} antinodes", sources.len());
 }
 
 fn main() {
-    println!("{}", part1_solution());
-    println!("{}", part2_solution());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("antinodes") => print_antinodes(&args[1..]),
+        _ => {
+            println!("{}", part1_solution());
+            println!("{}", part2_solution());
+        }
+    }
 }
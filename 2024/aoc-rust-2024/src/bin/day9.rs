@@ -1,110 +1,246 @@
-use std::iter;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    iter,
+};
 
 use aoc_rust_2024::io;
+use itertools::Itertools;
+
+// A run of contiguous blocks belonging to a single file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Span {
+    file_id: usize,
+    start: usize,
+    len: usize,
+}
 
-type FileSystem = Vec<Option<usize>>;
-
-fn parse_disk_map(disk_map: &str) -> FileSystem {
-    let block_size_iter = disk_map.chars().step_by(2);
-    let free_space_iter = disk_map.chars().skip(1).step_by(2).chain(iter::once('0'));
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Strategy {
+    // Move single blocks from the end of the disk into the leftmost free block.
+    BlockWise,
+    // Move whole files into the leftmost gap big enough to hold them.
+    FileWise,
+    // Move whole files into the smallest gap big enough to hold them.
+    BestFit,
+}
 
-    let mut fs = Vec::new();
-    for (block_id, (block_size, free_space)) in block_size_iter.zip(free_space_iter).enumerate() {
-        let block_size = block_size.to_digit(10).unwrap() as usize;
-        let free_space = free_space.to_digit(10).unwrap() as usize;
-        fs.extend(iter::repeat(Some(block_id)).take(block_size));
-        fs.extend(iter::repeat(None).take(free_space));
+impl From<&str> for Strategy {
+    fn from(value: &str) -> Self {
+        match value {
+            "block-wise" => Strategy::BlockWise,
+            "file-wise" => Strategy::FileWise,
+            "best-fit" => Strategy::BestFit,
+            _ => panic!("Unknown compaction strategy '{}'", value),
+        }
     }
-    fs
 }
 
-fn defrag(fs: &mut FileSystem) {
-    let advance = |mut start: usize, mut end: usize, fs: &FileSystem| {
-        while fs[start].is_some() {
-            start += 1;
-        }
-        while fs[end].is_none() {
-            end -= 1;
-        }
-        (start, end)
-    };
+#[derive(Clone, Debug)]
+struct Disk {
+    // sorted by start position
+    spans: Vec<Span>,
+    size: usize,
+}
 
-    let (mut start, mut end) = advance(0, fs.len() - 1, fs);
+impl From<&str> for Disk {
+    fn from(disk_map: &str) -> Self {
+        let block_size_iter = disk_map.chars().step_by(2);
+        let free_space_iter = disk_map.chars().skip(1).step_by(2).chain(iter::once('0'));
+
+        let mut spans = Vec::new();
+        let mut pos = 0;
+        for (file_id, (block_size, free_space)) in block_size_iter.zip(free_space_iter).enumerate()
+        {
+            let len = block_size.to_digit(10).unwrap() as usize;
+            let free_space = free_space.to_digit(10).unwrap() as usize;
+            if len > 0 {
+                spans.push(Span {
+                    file_id,
+                    start: pos,
+                    len,
+                });
+            }
+            pos += len + free_space;
+        }
 
-    // invariant: `start` is on free space, `end` is on a block
-    while end > start {
-        fs.swap(start, end);
-        (start, end) = advance(start, end, fs);
+        Self { spans, size: pos }
     }
 }
 
-fn defrag_and_compact(fs: &mut FileSystem) {
-    // invariant: `end` is one past the end of unprocessed data
-    let mut end = fs.len();
-    while end > 0 {
-        end -= 1;
+impl Disk {
+    // Free gaps between files as (start, len). Free space after the last file
+    // is not included.
+    fn gaps(&self) -> Vec<(usize, usize)> {
+        self.spans
+            .iter()
+            .tuple_windows()
+            .map(|(a, b)| (a.start + a.len, b.start - (a.start + a.len)))
+            .filter(|&(_, len)| len > 0)
+            .collect()
+    }
 
-        // skip free space at end of unprocessed data
-        while end > 0 && fs[end].is_none() {
-            end -= 1;
+    fn compact(&mut self, strategy: Strategy) {
+        match strategy {
+            Strategy::BlockWise => self.compact_blocks(),
+            Strategy::FileWise | Strategy::BestFit => self.compact_files(strategy),
         }
+        self.spans.sort_by_key(|span| span.start);
+    }
 
-        // scan file
-        let file_end = end;
-        let file_id = fs[end];
-        while end > 0 && fs[end - 1] == file_id {
-            end -= 1;
-        }
-        let file_start = end;
-        let file_size = file_end - file_start + 1;
-
-        // find free space near start
-        let mut i = 0;
-        while i < file_start {
-            while i < file_start && fs[i].is_some() {
-                i += 1;
+    fn compact_blocks(&mut self) {
+        let mut gaps: VecDeque<(usize, usize)> = self.gaps().into();
+        let mut compacted = Vec::new();
+
+        // Peel blocks off the end of the last file until the leftmost gap is
+        // no longer to the left of it.
+        while let Some(mut span) = self.spans.pop() {
+            while span.len > 0 {
+                let Some((gap_start, gap_len)) = gaps.front_mut() else {
+                    break;
+                };
+                if *gap_start > span.start {
+                    break;
+                }
+
+                let moved = span.len.min(*gap_len);
+                compacted.push(Span {
+                    file_id: span.file_id,
+                    start: *gap_start,
+                    len: moved,
+                });
+                span.len -= moved;
+                *gap_start += moved;
+                *gap_len -= moved;
+                if *gap_len == 0 {
+                    gaps.pop_front();
+                }
             }
 
-            let free_start = i;
-            while fs[i].is_none() {
-                i += 1;
+            if span.len > 0 {
+                compacted.push(span);
             }
-            let free_size = i - free_start;
-            if free_size >= file_size {
-                fs.copy_within(file_start..=file_end, free_start);
-                fs[file_start..=file_end].fill(None);
+            if gaps
+                .front()
+                .is_none_or(|&(gap_start, _)| gap_start > span.start)
+            {
                 break;
             }
         }
+
+        self.spans.extend(compacted);
     }
-}
 
-fn checksum(fs: &FileSystem) -> usize {
-    fs.iter()
-        .enumerate()
-        .filter_map(|(pos, &id)| id.map(|id| (pos, id)))
-        .fold(0, |total, (pos, id)| total + pos * id)
+    fn compact_files(&mut self, strategy: Strategy) {
+        // Bucket the gaps by size, keeping the leftmost gap of each size on top.
+        let gaps = self.gaps();
+        let max_gap_len = gaps.iter().map(|&(_, len)| len).max().unwrap_or(0);
+        let mut gaps_by_len = vec![BinaryHeap::new(); max_gap_len + 1];
+        for (start, len) in gaps {
+            gaps_by_len[len].push(Reverse(start));
+        }
+
+        // Each file is only moved once, in order of decreasing file ID.
+        let order = (0..self.spans.len())
+            .sorted_by_key(|&i| Reverse(self.spans[i].file_id))
+            .collect_vec();
+
+        for i in order {
+            let span = self.spans[i];
+            let mut candidates = (span.len..=max_gap_len).filter_map(|len| {
+                gaps_by_len[len]
+                    .peek()
+                    .map(|&Reverse(start)| (start, len))
+                    .filter(|&(start, _)| start < span.start)
+            });
+            let target = match strategy {
+                Strategy::FileWise => candidates.min(),
+                Strategy::BestFit => candidates.next(),
+                Strategy::BlockWise => unreachable!(),
+            };
+
+            if let Some((gap_start, gap_len)) = target {
+                gaps_by_len[gap_len].pop();
+                if gap_len > span.len {
+                    gaps_by_len[gap_len - span.len].push(Reverse(gap_start + span.len));
+                }
+                self.spans[i].start = gap_start;
+            }
+        }
+    }
+
+    fn checksum(&self) -> usize {
+        self.spans
+            .iter()
+            .map(|span| span.file_id * (span.len * span.start + span.len * (span.len - 1) / 2))
+            .sum()
+    }
+
+    // Draw the disk one character per block, like the puzzle description.
+    // File IDs only get a single digit, so larger IDs wrap around.
+    fn render(&self) -> String {
+        let mut blocks = vec!['.'; self.size];
+        for span in &self.spans {
+            let c = char::from_digit((span.file_id % 10) as u32, 10).unwrap();
+            blocks[span.start..span.start + span.len].fill(c);
+        }
+        blocks.into_iter().collect()
+    }
+
+    fn export(&self) -> String {
+        iter::once("file_id,start,len".to_string())
+            .chain(
+                self.spans
+                    .iter()
+                    .map(|span| format!("{},{},{}", span.file_id, span.start, span.len)),
+            )
+            .join("\n")
+    }
 }
 
-fn part1_solution() -> usize {
+fn parse_input() -> Disk {
     let input = io::get_puzzle_input(9);
-    let input = input.trim();
+    input.trim().into()
+}
 
-    let mut fs = parse_disk_map(input);
-    defrag(&mut fs);
-    checksum(&fs)
+fn part1_solution() -> usize {
+    let mut disk = parse_input();
+    disk.compact(Strategy::BlockWise);
+    disk.checksum()
 }
 
 fn part2_solution() -> usize {
-    let input = io::get_puzzle_input(9);
-    let input = input.trim();
+    let mut disk = parse_input();
+    disk.compact(Strategy::FileWise);
+    disk.checksum()
+}
 
-    let mut fs = parse_disk_map(input);
-    defrag_and_compact(&mut fs);
-    checksum(&fs)
+// Usage: render|export [--strategy block-wise|file-wise|best-fit]
+fn print_disk(mode: &str, args: &[String]) {
+    let strategy = match args {
+        [] => Strategy::FileWise,
+        [flag, strategy] if flag == "--strategy" => strategy.as_str().into(),
+        _ => panic!("Unknown arguments {:?}", args),
+    };
+
+    let mut disk = parse_input();
+    if mode == "render" {
+        println!("{}", disk.render());
+        disk.compact(strategy);
+        println!("{}", disk.render());
+    } else {
+        disk.compact(strategy);
+        println!("{}", disk.export());
+    }
 }
 
 fn main() {
-    println!("{}", part1_solution());
-    println!("{}", part2_solution());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some(mode @ ("render" | "export")) => print_disk(mode, &args[1..]),
+        _ => {
+            println!("{}", part1_solution());
+            println!("{}", part2_solution());
+        }
+    }
 }
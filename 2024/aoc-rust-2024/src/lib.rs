@@ -1,2 +1,6 @@
 pub mod helpers;
 pub mod io;
+pub mod order;
+pub mod wordsearch;
+
+pub use aoc_common::{grid, polygon};
@@ -0,0 +1,147 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    hash::Hash,
+};
+
+// A set of "a comes before b" rules. The rules don't have to cover every pair
+// of items, and nothing stops them from contradicting each other, so sorting
+// reports any cycle it runs into rather than giving up quietly.
+#[derive(Clone, Debug, Default)]
+pub struct PartialOrder<T> {
+    successors: HashMap<T, HashSet<T>>,
+    predecessors: HashMap<T, HashSet<T>>,
+}
+
+// Items that the rules require to come before themselves, in rule order: each
+// item must come before the next, and the last before the first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cycle<T>(pub Vec<T>);
+
+impl<T: Copy + Eq + Hash + Ord> PartialOrder<T> {
+    pub fn new(rules: impl IntoIterator<Item = (T, T)>) -> Self {
+        let mut order = Self {
+            successors: HashMap::new(),
+            predecessors: HashMap::new(),
+        };
+        for (before, after) in rules {
+            order.successors.entry(before).or_default().insert(after);
+            order.predecessors.entry(after).or_default().insert(before);
+        }
+        order
+    }
+
+    pub fn must_precede(&self, a: T, b: T) -> bool {
+        self.successors
+            .get(&a)
+            .is_some_and(|after| after.contains(&b))
+    }
+
+    // Every item mentioned by a rule, smallest first.
+    pub fn items(&self) -> Vec<T> {
+        let mut items: Vec<T> = self
+            .successors
+            .keys()
+            .chain(self.predecessors.keys())
+            .copied()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        items.sort();
+        items
+    }
+
+    // The first rule `items` breaks, as (before, after), reading left to
+    // right. That is, `after` is the first item with something later on that
+    // should have come before it.
+    pub fn first_violation(&self, items: &[T]) -> Option<(T, T)> {
+        items.iter().enumerate().find_map(|(i, &a)| {
+            items[i + 1..]
+                .iter()
+                .find(|&&b| self.must_precede(b, a))
+                .map(|&b| (b, a))
+        })
+    }
+
+    pub fn is_ordered(&self, items: &[T]) -> bool {
+        self.first_violation(items).is_none()
+    }
+
+    // Put `items` in an order that follows every rule between them. Where
+    // the rules leave a choice, the smallest item goes first, so the result
+    // doesn't depend on the order `items` started in. Repeated items stay
+    // repeated, next to each other.
+    pub fn sort(&self, items: &[T]) -> Result<Vec<T>, Cycle<T>> {
+        let mut copies: HashMap<T, usize> = HashMap::new();
+        for &item in items {
+            *copies.entry(item).or_insert(0) += 1;
+        }
+        let included: HashSet<T> = copies.keys().copied().collect();
+        let neighbours = |map: &HashMap<T, HashSet<T>>, item: T| -> Vec<T> {
+            map.get(&item)
+                .into_iter()
+                .flatten()
+                .filter(|other| included.contains(other))
+                .copied()
+                .collect()
+        };
+
+        let mut num_before: HashMap<T, usize> = included
+            .iter()
+            .map(|&item| (item, neighbours(&self.predecessors, item).len()))
+            .collect();
+        let mut ready: BinaryHeap<Reverse<T>> = num_before
+            .iter()
+            .filter(|(_, &n)| n == 0)
+            .map(|(&item, _)| Reverse(item))
+            .collect();
+
+        let mut sorted = vec![];
+        while let Some(Reverse(item)) = ready.pop() {
+            sorted.extend(std::iter::repeat_n(item, copies[&item]));
+            for after in neighbours(&self.successors, item) {
+                let n = num_before.get_mut(&after).unwrap();
+                *n -= 1;
+                if *n == 0 {
+                    ready.push(Reverse(after));
+                }
+            }
+        }
+
+        if sorted.len() == items.len() {
+            return Ok(sorted);
+        }
+
+        // Everything left over still has something left over that needs to
+        // come before it, so walking backwards through those must eventually
+        // come round in a loop.
+        let remaining: HashSet<T> = included
+            .into_iter()
+            .filter(|item| num_before[item] > 0)
+            .collect();
+        let mut path = vec![*remaining.iter().min().unwrap()];
+        let mut seen = HashMap::from([(path[0], 0)]);
+        loop {
+            let item = *path.last().unwrap();
+            let before = self.predecessors[&item]
+                .iter()
+                .filter(|other| remaining.contains(other))
+                .min()
+                .copied()
+                .unwrap();
+            if let Some(&start) = seen.get(&before) {
+                let mut cycle = path[start..].to_vec();
+                cycle.reverse();
+                return Err(Cycle(cycle));
+            }
+            seen.insert(before, path.len());
+            path.push(before);
+        }
+    }
+
+    // Whether the rules pin down exactly one order for all of their items.
+    pub fn is_total_order(&self) -> Result<bool, Cycle<T>> {
+        let sorted = self.sort(&self.items())?;
+        Ok(sorted.windows(2).all(|w| self.must_precede(w[0], w[1])))
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+
+use itertools::Itertools;
+
+use crate::grid::{GridBitSet, Position};
+
+// Offsets are (row, col), and can be negative.
+type Offset = (isize, isize);
+
+const COMPASS: [Offset; 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+// Which way a stencil has been turned, given by where its rows and columns
+// end up. Moving one column to the right in the stencil moves `right` in the
+// grid, and moving one row down moves `down`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Orientation {
+    pub right: Offset,
+    pub down: Offset,
+}
+
+impl Orientation {
+    fn apply(&self, (row, col): Offset) -> Offset {
+        (
+            row * self.down.0 + col * self.right.0,
+            row * self.down.1 + col * self.right.1,
+        )
+    }
+}
+
+// A pattern of letters to look for, where some cells can be left as
+// wildcards that match anything.
+#[derive(Clone, Debug)]
+pub struct Stencil {
+    // The letters that have to match, relative to the top left.
+    cells: Vec<(Offset, u8)>,
+    height: usize,
+}
+
+impl Stencil {
+    // One row per line. `wildcard` cells match any letter.
+    pub fn new(pattern: &str, wildcard: Option<u8>) -> Self {
+        let lines = pattern.lines().collect_vec();
+        let cells = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                line.bytes()
+                    .enumerate()
+                    .filter(|&(_, c)| Some(c) != wildcard)
+                    .map(move |(col, c)| ((row as isize, col as isize), c))
+            })
+            .collect();
+        Self {
+            cells,
+            height: lines.len(),
+        }
+    }
+
+    pub fn word(word: &str) -> Self {
+        Self::new(word, None)
+    }
+
+    // A single line can be read in any of the 8 compass directions. Anything
+    // bigger can be turned to face 4 ways and flipped over, which is also 8
+    // orientations, less any that give the same pattern back.
+    pub fn orientations(&self) -> Vec<Orientation> {
+        let all = if self.height == 1 {
+            COMPASS
+                .iter()
+                .map(|&(dr, dc)| Orientation {
+                    right: (dr, dc),
+                    down: (dc, -dr),
+                })
+                .collect_vec()
+        } else {
+            COMPASS
+                .iter()
+                .step_by(2)
+                .flat_map(|&(dr, dc)| {
+                    [(dc, -dr), (-dc, dr)].map(|down| Orientation {
+                        right: (dr, dc),
+                        down,
+                    })
+                })
+                .collect_vec()
+        };
+
+        // Two orientations give the same pattern if they only differ by
+        // where it sits relative to the anchor.
+        let mut seen = HashSet::new();
+        all.into_iter()
+            .filter(|o| {
+                let cells = self
+                    .cells
+                    .iter()
+                    .map(|&(offset, c)| (o.apply(offset), c))
+                    .collect_vec();
+                let min_row = cells.iter().map(|&((row, _), _)| row).min();
+                let min_col = cells.iter().map(|&((_, col), _)| col).min();
+                let shape: Vec<(Offset, u8)> = cells
+                    .into_iter()
+                    .map(|((row, col), c)| ((row - min_row.unwrap(), col - min_col.unwrap()), c))
+                    .sorted()
+                    .collect();
+                seen.insert(shape)
+            })
+            .collect()
+    }
+}
+
+// A place where a stencil fits the grid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+    // Where the stencil's top left cell landed.
+    pub anchor: Position,
+    pub orientation: Orientation,
+    // The grid cells covered by the stencil's letters.
+    pub cells: Vec<Position>,
+}
+
+#[derive(Clone, Debug)]
+pub struct WordGrid {
+    rows: Vec<Vec<u8>>,
+}
+
+impl From<&str> for WordGrid {
+    fn from(input: &str) -> Self {
+        Self {
+            rows: input.lines().map(|line| line.as_bytes().to_vec()).collect(),
+        }
+    }
+}
+
+impl WordGrid {
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
+    fn get(&self, (row, col): Offset) -> Option<u8> {
+        let row = self.rows.get(usize::try_from(row).ok()?)?;
+        row.get(usize::try_from(col).ok()?).copied()
+    }
+
+    // Every place the stencil fits, in any orientation, row by row.
+    pub fn search(&self, stencil: &Stencil) -> Vec<Match> {
+        let orientations = stencil.orientations();
+        let mut matches = vec![];
+
+        for row in 0..self.height() {
+            for col in 0..self.width() {
+                for &orientation in &orientations {
+                    let cells: Option<Vec<Position>> = stencil
+                        .cells
+                        .iter()
+                        .map(|&(offset, c)| {
+                            let (dr, dc) = orientation.apply(offset);
+                            let pos = (row as isize + dr, col as isize + dc);
+                            (self.get(pos)? == c).then_some((pos.0 as usize, pos.1 as usize))
+                        })
+                        .collect();
+                    if let Some(cells) = cells {
+                        matches.push(Match {
+                            anchor: (row, col),
+                            orientation,
+                            cells,
+                        });
+                    }
+                }
+            }
+        }
+        matches
+    }
+
+    // The grid with everything outside of the matches blanked out.
+    pub fn render(&self, matches: &[Match]) -> String {
+        let mut shown = GridBitSet::new(self.height(), self.width());
+        shown.extend(matches.iter().flat_map(|m| m.cells.iter().copied()));
+
+        self.rows
+            .iter()
+            .enumerate()
+            .map(|(row, line)| {
+                line.iter()
+                    .enumerate()
+                    .map(|(col, &c)| {
+                        if shown.contains((row, col)) {
+                            c as char
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .join("\n")
+    }
+}
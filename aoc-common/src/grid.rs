@@ -0,0 +1,295 @@
+use std::ops::{BitAnd, BitOr, BitXor, Sub};
+
+// Positions are (row, col).
+pub type Position = (usize, usize);
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+// A set of positions on a fixed size grid, stored as one bit per cell. A
+// drop-in replacement for `HashSet<Position>` in flood fills and the like
+// that doesn't need to hash anything.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GridBitSet {
+    words: Vec<u64>,
+    height: usize,
+    width: usize,
+}
+
+impl GridBitSet {
+    pub fn new(height: usize, width: usize) -> Self {
+        Self {
+            words: vec![0; (height * width).div_ceil(WORD_BITS)],
+            height,
+            width,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    fn offset(&self, (row, col): Position) -> Option<(usize, u64)> {
+        if row < self.height && col < self.width {
+            let i = row * self.width + col;
+            Some((i / WORD_BITS, 1 << (i % WORD_BITS)))
+        } else {
+            None
+        }
+    }
+
+    // Returns whether the position was newly inserted, like `HashSet::insert`.
+    pub fn insert(&mut self, pos: Position) -> bool {
+        let (word, bit) = self
+            .offset(pos)
+            .unwrap_or_else(|| panic!("Position {:?} is off the grid!", pos));
+        let is_new = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        is_new
+    }
+
+    pub fn remove(&mut self, pos: Position) -> bool {
+        match self.offset(pos) {
+            Some((word, bit)) => {
+                let was_present = self.words[word] & bit != 0;
+                self.words[word] &= !bit;
+                was_present
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, pos: Position) -> bool {
+        self.offset(pos)
+            .is_some_and(|(word, bit)| self.words[word] & bit != 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    // Positions in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = Position> + '_ {
+        self.words.iter().enumerate().flat_map(move |(i, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(i * WORD_BITS + bit)
+            })
+            .map(move |idx| (idx / self.width, idx % self.width))
+        })
+    }
+
+    fn check_same_shape(&self, other: &Self) {
+        if (self.height, self.width) != (other.height, other.width) {
+            panic!("Grids are not the same size!");
+        }
+    }
+
+    fn combine_with(&mut self, other: &Self, op: impl Fn(u64, u64) -> u64) {
+        self.check_same_shape(other);
+        for (a, &b) in self.words.iter_mut().zip(&other.words) {
+            *a = op(*a, b);
+        }
+    }
+
+    pub fn union_with(&mut self, other: &Self) {
+        self.combine_with(other, |a, b| a | b);
+    }
+
+    pub fn intersect_with(&mut self, other: &Self) {
+        self.combine_with(other, |a, b| a & b);
+    }
+
+    pub fn difference_with(&mut self, other: &Self) {
+        self.combine_with(other, |a, b| a & !b);
+    }
+
+    pub fn symmetric_difference_with(&mut self, other: &Self) {
+        self.combine_with(other, |a, b| a ^ b);
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.check_same_shape(other);
+        self.words
+            .iter()
+            .zip(&other.words)
+            .all(|(a, b)| a & !b == 0)
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.check_same_shape(other);
+        self.words.iter().zip(&other.words).all(|(a, b)| a & b == 0)
+    }
+}
+
+impl Extend<Position> for GridBitSet {
+    fn extend<I: IntoIterator<Item = Position>>(&mut self, iter: I) {
+        for pos in iter {
+            self.insert(pos);
+        }
+    }
+}
+
+impl BitOr for &GridBitSet {
+    type Output = GridBitSet;
+
+    fn bitor(self, rhs: Self) -> GridBitSet {
+        let mut res = self.clone();
+        res.union_with(rhs);
+        res
+    }
+}
+
+impl BitAnd for &GridBitSet {
+    type Output = GridBitSet;
+
+    fn bitand(self, rhs: Self) -> GridBitSet {
+        let mut res = self.clone();
+        res.intersect_with(rhs);
+        res
+    }
+}
+
+impl Sub for &GridBitSet {
+    type Output = GridBitSet;
+
+    fn sub(self, rhs: Self) -> GridBitSet {
+        let mut res = self.clone();
+        res.difference_with(rhs);
+        res
+    }
+}
+
+impl BitXor for &GridBitSet {
+    type Output = GridBitSet;
+
+    fn bitxor(self, rhs: Self) -> GridBitSet {
+        let mut res = self.clone();
+        res.symmetric_difference_with(rhs);
+        res
+    }
+}
+
+// A map from positions on a fixed size grid to values, stored densely. A
+// drop-in replacement for `HashMap<Position, T>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GridMap<T> {
+    data: Vec<Option<T>>,
+    height: usize,
+    width: usize,
+}
+
+impl<T> GridMap<T> {
+    pub fn new(height: usize, width: usize) -> Self {
+        Self {
+            data: std::iter::repeat_with(|| None)
+                .take(height * width)
+                .collect(),
+            height,
+            width,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    fn offset(&self, (row, col): Position) -> Option<usize> {
+        if row < self.height && col < self.width {
+            Some(row * self.width + col)
+        } else {
+            None
+        }
+    }
+
+    // Returns the old value at the position, like `HashMap::insert`.
+    pub fn insert(&mut self, pos: Position, value: T) -> Option<T> {
+        let i = self
+            .offset(pos)
+            .unwrap_or_else(|| panic!("Position {:?} is off the grid!", pos));
+        self.data[i].replace(value)
+    }
+
+    pub fn remove(&mut self, pos: Position) -> Option<T> {
+        self.offset(pos).and_then(|i| self.data[i].take())
+    }
+
+    pub fn get(&self, pos: Position) -> Option<&T> {
+        self.offset(pos).and_then(|i| self.data[i].as_ref())
+    }
+
+    pub fn get_mut(&mut self, pos: Position) -> Option<&mut T> {
+        self.offset(pos).and_then(|i| self.data[i].as_mut())
+    }
+
+    pub fn get_or_insert_with(&mut self, pos: Position, f: impl FnOnce() -> T) -> &mut T {
+        let i = self
+            .offset(pos)
+            .unwrap_or_else(|| panic!("Position {:?} is off the grid!", pos));
+        self.data[i].get_or_insert_with(f)
+    }
+
+    pub fn contains_key(&self, pos: Position) -> bool {
+        self.get(pos).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.iter().filter(|v| v.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.iter().all(|v| v.is_none())
+    }
+
+    // Entries in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        self.data
+            .iter()
+            .enumerate()
+            .filter_map(|(i, v)| v.as_ref().map(|v| ((i / self.width, i % self.width), v)))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = Position> + '_ {
+        self.iter().map(|(pos, _)| pos)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    // The positions that have a value, as a bitset.
+    pub fn key_set(&self) -> GridBitSet {
+        let mut set = GridBitSet::new(self.height, self.width);
+        set.extend(self.keys());
+        set
+    }
+}
+
+impl<T> std::ops::Index<Position> for GridMap<T> {
+    type Output = T;
+
+    fn index(&self, pos: Position) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("No value at position {:?}", pos))
+    }
+}
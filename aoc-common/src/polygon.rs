@@ -0,0 +1,251 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+// Points are (row, col), so rows grow downwards.
+pub type Point = (i64, i64);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    fn delta(&self) -> Point {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+        }
+    }
+
+    fn turn_right(&self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+}
+
+// A closed rectilinear polygon on the integer lattice, stored as its corners
+// in order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Polygon {
+    vertices: Vec<Point>,
+}
+
+impl Polygon {
+    // Follow `moves` from the origin. The moves must lead back to the origin.
+    pub fn from_moves<I>(moves: I) -> Self
+    where
+        I: IntoIterator<Item = (Direction, i64)>,
+    {
+        let mut pos = (0, 0);
+        let mut vertices = vec![];
+        for (dir, count) in moves {
+            vertices.push(pos);
+            let (dr, dc) = dir.delta();
+            pos = (pos.0 + dr * count, pos.1 + dc * count);
+        }
+
+        if pos != (0, 0) {
+            panic!("Moves do not form a closed loop!");
+        }
+        Self::from_vertices(vertices)
+    }
+
+    // Build a polygon from a closed loop of boundary points, each one next to
+    // the one before it (e.g. the tiles of a pipe loop).
+    pub fn from_path(path: &[Point]) -> Self {
+        Self::from_vertices(path.to_vec())
+    }
+
+    // Trace the outlines of a set of unit cells, where cell (r, c) covers the
+    // square from point (r, c) to point (r + 1, c + 1). Outer outlines run
+    // clockwise and the outlines of holes run anticlockwise. Cells that only
+    // touch at a corner are given separate outlines.
+    pub fn from_cells<I>(cells: I) -> Vec<Self>
+    where
+        I: IntoIterator<Item = Point>,
+    {
+        let cells: HashSet<Point> = cells.into_iter().collect();
+
+        // Walk around each cell clockwise, keeping the edges that face a cell
+        // outside of the set.
+        let mut edges: HashMap<Point, Vec<Direction>> = HashMap::new();
+        for &(r, c) in &cells {
+            let sides = [
+                ((r - 1, c), (r, c), Direction::Right),
+                ((r, c + 1), (r, c + 1), Direction::Down),
+                ((r + 1, c), (r + 1, c + 1), Direction::Left),
+                ((r, c - 1), (r + 1, c), Direction::Up),
+            ];
+            for (nbr, start, dir) in sides {
+                if !cells.contains(&nbr) {
+                    edges.entry(start).or_default().push(dir);
+                }
+            }
+        }
+
+        let mut outlines = vec![];
+        let mut used = HashSet::new();
+        for (&start, dirs) in edges.iter().sorted_by_key(|(&pos, _)| pos) {
+            for &dir in dirs {
+                if used.contains(&(start, dir)) {
+                    continue;
+                }
+
+                let mut vertices = vec![];
+                let (mut pos, mut dir) = (start, dir);
+                while used.insert((pos, dir)) {
+                    vertices.push(pos);
+                    let (dr, dc) = dir.delta();
+                    pos = (pos.0 + dr, pos.1 + dc);
+
+                    // Where two outlines meet at a corner, turn right to keep
+                    // hugging the same cell.
+                    let options = &edges[&pos];
+                    dir = if options.len() == 1 {
+                        options[0]
+                    } else {
+                        dir.turn_right()
+                    };
+                }
+                outlines.push(Self::from_vertices(vertices));
+            }
+        }
+
+        outlines
+    }
+
+    // Only keep points where the boundary changes direction.
+    fn from_vertices(vertices: Vec<Point>) -> Self {
+        let vertices = vertices
+            .iter()
+            .circular_tuple_windows()
+            .filter(|(a, b, c)| !(a.0 == b.0 && b.0 == c.0 || a.1 == b.1 && b.1 == c.1))
+            .map(|(_, &b, _)| b)
+            .collect();
+        Self { vertices }
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    // Twice the area given by the shoelace formula. Positive when the vertices
+    // run clockwise.
+    fn double_signed_area(&self) -> i64 {
+        self.vertices
+            .iter()
+            .circular_tuple_windows()
+            .map(|(&(r1, c1), &(r2, c2))| r2 * c1 - r1 * c2)
+            .sum()
+    }
+
+    pub fn is_clockwise(&self) -> bool {
+        self.double_signed_area() > 0
+    }
+
+    // The area of a rectilinear lattice polygon is always a whole number.
+    pub fn signed_area(&self) -> i64 {
+        self.double_signed_area() / 2
+    }
+
+    pub fn area(&self) -> u64 {
+        self.signed_area().unsigned_abs()
+    }
+
+    // Also the number of lattice points on the boundary.
+    pub fn boundary_length(&self) -> u64 {
+        self.vertices
+            .iter()
+            .circular_tuple_windows()
+            .map(|(&(r1, c1), &(r2, c2))| r1.abs_diff(r2) + c1.abs_diff(c2))
+            .sum()
+    }
+
+    // Pick's theorem: A = i + b/2 - 1
+    pub fn interior_points(&self) -> u64 {
+        self.area() + 1 - self.boundary_length() / 2
+    }
+
+    pub fn lattice_points(&self) -> u64 {
+        self.interior_points() + self.boundary_length()
+    }
+
+    // Every corner of a rectilinear polygon starts a new side.
+    pub fn num_sides(&self) -> usize {
+        self.vertices.len()
+    }
+
+    // The top left and bottom right corners of the smallest enclosing box.
+    pub fn bounding_box(&self) -> (Point, Point) {
+        let (min_row, max_row) = self
+            .vertices
+            .iter()
+            .map(|p| p.0)
+            .minmax()
+            .into_option()
+            .unwrap();
+        let (min_col, max_col) = self
+            .vertices
+            .iter()
+            .map(|p| p.1)
+            .minmax()
+            .into_option()
+            .unwrap();
+        ((min_row, min_col), (max_row, max_col))
+    }
+
+    // Mark every lattice point inside or on the polygon. grid[0][0] is the
+    // top left corner of the bounding box.
+    pub fn rasterise(&self) -> Vec<Vec<bool>> {
+        let ((min_row, min_col), (max_row, max_col)) = self.bounding_box();
+        let height = (max_row - min_row + 1) as usize;
+        let width = (max_col - min_col + 1) as usize;
+        let mut grid = vec![vec![false; width]; height];
+
+        let edges = self.vertices.iter().circular_tuple_windows().collect_vec();
+
+        for (&(r1, c1), &(r2, c2)) in &edges {
+            for r in r1.min(r2)..=r1.max(r2) {
+                for c in c1.min(c2)..=c1.max(c2) {
+                    grid[(r - min_row) as usize][(c - min_col) as usize] = true;
+                }
+            }
+        }
+
+        // Cast a ray left to right along each row, counting the vertical
+        // edges it crosses. Edges include their top end but not their bottom
+        // end, so a ray through a corner is counted correctly.
+        for row in min_row..=max_row {
+            let crossings = edges
+                .iter()
+                .filter(|((r1, c1), (r2, c2))| {
+                    c1 == c2 && (*r1.min(r2)..*r1.max(r2)).contains(&row)
+                })
+                .map(|((_, c), _)| *c)
+                .sorted()
+                .collect_vec();
+
+            let mut crossed = 0;
+            for col in min_col..=max_col {
+                while crossed < crossings.len() && crossings[crossed] < col {
+                    crossed += 1;
+                }
+                if crossed % 2 == 1 {
+                    grid[(row - min_row) as usize][(col - min_col) as usize] = true;
+                }
+            }
+        }
+
+        grid
+    }
+}